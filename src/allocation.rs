@@ -7,28 +7,87 @@
 
 //! Types for safely allocating memory.
 
-use core::{fmt, intrinsics, mem};
+#[cfg(feature = "alloc")]
+use alloc::alloc as global;
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+use core::{cmp, fmt, hint, mem, ptr, slice};
+use core::alloc::Layout;
+use core::borrow::{Borrow, BorrowMut};
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::mem::{align_of, size_of};
+use core::hash::{Hash, Hasher};
+use core::ops::{Deref, DerefMut, Index, IndexMut, Range};
+use super::error::Error;
 use super::heap;
-use core::ptr::Unique;
+use core::ptr::NonNull;
+use core::slice::SliceIndex;
 use super::result::Result;
 
 #[allow(missing_copy_implementations)]
 /// An owned, allocated block of memory.
 pub struct Allocation {
     /// A raw pointer to the allocated block of memory.
-    ptr: Unique<u8>,
-    /// The length in bytes of the allocation.
+    ptr: NonNull<u8>,
+    /// The length in bytes of the allocation. Every byte within this length is initialized.
     len: usize,
+    /// The length in bytes of the allocated block of memory, which may exceed `len`.
+    capacity: usize,
     /// The alignment in bytes of the allocation.
     align: usize,
+    /// Whether the allocation is zeroed before being freed.
+    secure: bool,
 }
 
-#[cfg_attr(feature = "clippy", allow(len_without_is_empty))]
 impl Allocation {
     #[inline]
-    /// Allocates a block of memory using the specified length and alignment.
+    /// Allocates a block of memory using the specified length and alignment, with all bytes
+    /// initialized to zero.
+    ///
+    /// Every byte within the length of an allocation can be read via safe methods such as
+    /// `Allocation::as_slice`, so unlike `heap::allocate`, this never leaves the memory
+    /// uninitialized, and is equivalent to `Allocation::zeroed`.
     pub fn new(len: usize, align: usize) -> Result<Allocation> {
-        unsafe { heap::allocate(len, align).map(|ptr| Self::from_raw(ptr, len, align)) }
+        Allocation::zeroed(len, align)
+    }
+
+    #[inline]
+    /// Allocates a block of memory using the specified length and alignment, leaving its contents
+    /// uninitialized.
+    ///
+    /// This must only be used where every byte within the length of the allocation is written
+    /// before the allocation is exposed to safe code.
+    unsafe fn new_uninit(len: usize, align: usize) -> Result<Allocation> {
+        heap::allocate(len, align).map(|ptr| Self::from_raw(ptr, len, align))
+    }
+
+    #[inline]
+    /// As `Allocation::new_uninit`, except that a length of zero produces an empty, zero-capacity
+    /// allocation backed by a dangling, suitably-aligned pointer, rather than an error.
+    ///
+    /// This is for copying allocations which may have been emptied, for example via
+    /// `Allocation::truncate`, since the copy of an empty allocation is itself empty.
+    unsafe fn new_uninit_or_empty(len: usize, align: usize) -> Result<Allocation> {
+        if len == 0 {
+            Ok(Self::from_raw(align as *mut u8, 0, align))
+        } else {
+            Allocation::new_uninit(len, align)
+        }
+    }
+
+    #[inline]
+    /// Allocates a block of memory using the specified length and alignment, provided the length
+    /// does not exceed `max` bytes.
+    ///
+    /// Returns `Error::NotEnoughMemory` without calling the backing allocator if `len` is greater
+    /// than `max`. This allows a memory budget to be enforced per request.
+    pub fn new_bounded(len: usize, align: usize, max: usize) -> Result<Allocation> {
+        if len > max {
+            Err(Error::NotEnoughMemory)
+        } else {
+            Allocation::new(len, align)
+        }
     }
 
     #[inline]
@@ -38,119 +97,1800 @@ impl Allocation {
         unsafe { heap::allocate_zeroed(len, align).map(|ptr| Self::from_raw(ptr, len, align)) }
     }
 
+    #[inline]
+    /// Allocates a block of memory using the specified length and alignment, returning `None` on
+    /// failure.
+    ///
+    /// This is a convenience wrapper around `Allocation::new` for callers that do not need to know
+    /// why an allocation failed.
+    pub fn try_new(len: usize, align: usize) -> Option<Allocation> {
+        Allocation::new(len, align).ok()
+    }
+
+    #[inline]
+    /// Allocates a block of memory with all bytes initialized to zero, using the specified length
+    /// and alignment, returning `None` on failure.
+    ///
+    /// This is a convenience wrapper around `Allocation::zeroed` for callers that do not need to
+    /// know why an allocation failed.
+    pub fn try_zeroed(len: usize, align: usize) -> Option<Allocation> {
+        Allocation::zeroed(len, align).ok()
+    }
+
+    #[inline]
+    /// Allocates a block of memory using the specified length, and an alignment chosen via
+    /// `heap::alignment_for`.
+    pub fn new_auto(len: usize) -> Result<Allocation> {
+        Allocation::new(len, heap::alignment_for(len))
+    }
+
+    #[inline]
+    /// Allocates a block of memory with all bytes initialized to zero, using the specified length,
+    /// and an alignment chosen via `heap::alignment_for`.
+    pub fn zeroed_auto(len: usize) -> Result<Allocation> {
+        Allocation::zeroed(len, heap::alignment_for(len))
+    }
+
+    #[inline]
+    /// Allocates a block of memory with all bytes initialized to zero, using the specified
+    /// capacity and alignment, and a length of zero.
+    ///
+    /// The zeroed bytes can later be made part of the allocation via `Allocation::set_len`.
+    ///
+    /// Returns `Error::ZeroLength` if the capacity is zero.
+    pub fn with_capacity_zeroed(capacity: usize, align: usize) -> Result<Allocation> {
+        Allocation::zeroed(capacity, align).map(
+            |mut alloc| {
+                alloc.len = 0;
+                alloc
+            }
+        )
+    }
+
+    #[inline]
+    /// Allocates a block of memory using the specified alignment, with the same length and
+    /// contents as a byte slice.
+    ///
+    /// Returns `Error::ZeroLength` if the slice is empty.
+    pub fn from_bytes(src: &[u8], align: usize) -> Result<Allocation> {
+        unsafe {
+            Allocation::new_uninit(src.len(), align).map(
+                |mut alloc| {
+                    ptr::copy_nonoverlapping(src.as_ptr(), alloc.as_mut_ptr(), src.len());
+                    alloc
+                }
+            )
+        }
+    }
+
+    /// Allocates a block of memory using the specified alignment, holding the contents of each
+    /// byte slice in sequence.
+    ///
+    /// Returns `Error::CapacityOverflow` if the total length of the slices would overflow, or
+    /// `Error::ZeroLength` if it is zero.
+    pub fn concat(parts: &[&[u8]], align: usize) -> Result<Allocation> {
+        parts
+            .iter()
+            .try_fold(0usize, |total, part| total.checked_add(part.len()))
+            .ok_or(Error::CapacityOverflow)
+            .and_then(|len| unsafe { Allocation::new_uninit(len, align) })
+            .map(
+                |mut alloc| {
+                    let mut offset = 0;
+                    for part in parts {
+                        unsafe {
+                            ptr::copy_nonoverlapping(
+                                part.as_ptr(),
+                                alloc.as_mut_ptr().add(offset),
+                                part.len(),
+                            );
+                        }
+                        offset += part.len();
+                    }
+                    alloc
+                }
+            )
+    }
+
+    #[inline]
+    /// Allocates a block of memory large enough to hold `count` values of type `T`, aligned for
+    /// `T`, with all bytes initialized to zero.
+    ///
+    /// Returns `Error::CapacityOverflow` if the length in bytes of the array would overflow.
+    pub fn new_array<T>(count: usize) -> Result<Allocation> {
+        unsafe {
+            heap::allocate_array(count, size_of::<T>(), align_of::<T>()).map(
+                |ptr| {
+                    let len = count * size_of::<T>();
+                    ptr::write_bytes(ptr, 0, len);
+                    Self::from_raw(ptr, len, align_of::<T>())
+                }
+            )
+        }
+    }
+
+    /// Allocates a block of memory using the specified alignment, holding the bytes yielded by an
+    /// iterator.
+    ///
+    /// The iterator's size hint is used to size the initial allocation, so an iterator that
+    /// reports its exact length is collected without reallocating. Otherwise, the allocation grows
+    /// geometrically as bytes are yielded, and is shrunk to fit once the iterator is exhausted.
+    ///
+    /// Returns `Error::ZeroLength` if the iterator yields no bytes.
+    pub fn try_from_iter<I: IntoIterator<Item = u8>>(iter: I, align: usize) -> Result<Allocation> {
+        let mut iter = iter.into_iter();
+        let first = iter.next().ok_or(Error::ZeroLength)?;
+        let hint = iter.size_hint().0.saturating_add(1);
+        let mut alloc = unsafe { Allocation::new_uninit(hint, align)? };
+        alloc.len = 0;
+        alloc.push(first)?;
+        for byte in iter {
+            alloc.push(byte)?;
+        }
+        alloc.shrink_to_fit().map(|_| alloc)
+    }
+
+    #[inline]
+    /// Allocates a block of memory using the length and alignment of the specified layout.
+    pub fn from_layout(layout: Layout) -> Result<Allocation> {
+        Allocation::new(layout.size(), layout.align())
+    }
+
+    #[inline]
+    /// Allocates a block of memory using the specified length and alignment, which is overwritten
+    /// with zeros before being freed.
+    ///
+    /// The zeroing is performed with volatile writes so that it cannot be optimized away. This is
+    /// intended for holding secrets such as keys and passwords, but only guarantees that they are
+    /// not left behind in freed heap memory; it offers no protection against the memory being
+    /// swapped to disk or captured in a core dump.
+    pub fn new_secure(len: usize, align: usize) -> Result<Allocation> {
+        Allocation::new(len, align).map(
+            |mut alloc| {
+                alloc.secure = true;
+                alloc
+            }
+        )
+    }
+
     #[inline]
     /// Takes ownership of a raw pointer, length and alignment, and treats the three as an
     /// existing allocation.
     ///
-    /// This is unsafe because it assumes that the pointer refers to memory allocated via the Rust
-    /// allocation model using the given length and alignment. Undefined behavior will occur if
+    /// # Safety
+    ///
+    /// This is unsafe because it assumes that the pointer refers to memory allocated via this
+    /// crate, such as a pointer returned by `Allocation::into_raw`, using the given length and
+    /// alignment, and that all `len` bytes of it are initialized. Undefined behavior will occur if
     /// these assumptions do not hold true.
+    ///
+    /// As an exception, if `len` is zero, the pointer is never freed, and only needs to be
+    /// non-null.
     pub unsafe fn from_raw(ptr: *mut u8, len: usize, align: usize) -> Allocation {
+        debug_assert!(!ptr.is_null());
         Allocation {
-            ptr: Unique::new(ptr),
-            len: len,
-            align: align,
+            ptr: NonNull::new_unchecked(ptr),
+            len,
+            capacity: len,
+            align,
+            secure: false,
         }
     }
 
-    #[cfg_attr(feature = "clippy", allow(mem_forget))]
+    #[inline]
+    /// Takes ownership of a non-null pointer and a layout, and treats the two as an existing
+    /// allocation with the length and alignment of the layout.
+    ///
+    /// # Safety
+    ///
+    /// This is unsafe because it assumes that the pointer refers to memory allocated via this
+    /// crate, such as a pointer returned by `Allocation::into_raw_parts`, using the given layout,
+    /// and that every byte of it is initialized. Undefined behavior will occur if these assumptions
+    /// do not hold true.
+    pub unsafe fn from_raw_parts(ptr: NonNull<u8>, layout: Layout) -> Allocation {
+        Self::from_raw(ptr.as_ptr(), layout.size(), layout.align())
+    }
+
+    #[allow(clippy::mem_forget)]
     #[inline]
     /// Consumes an allocation without freeing associated memory, returning its pointer, length
     /// and alignment.
     ///
+    /// The returned length is the capacity of the allocation, not its logical length, since that is
+    /// what the block of memory must be freed with. Any bytes beyond the logical length are zeroed
+    /// first, so that every byte of the block is initialized.
+    ///
     /// Care must be taken to ensure that the memory is correctly freed after calling this method.
     /// This can be done by reconstructing the allocation via `Allocation::from_raw` and dropping
-    /// it immediately afterwards.
-    pub fn into_raw(self) -> (*mut u8, usize, usize) {
-        let ret = (self.ptr.as_ptr(), self.len, self.align);
+    /// it immediately afterwards. The reconstructed allocation has a length equal to the returned
+    /// capacity, and is never zeroed before being freed, even if the original allocation was
+    /// created via `Allocation::new_secure`.
+    pub fn into_raw(mut self) -> (*mut u8, usize, usize) {
+        self.zero_spare_capacity();
+        let ret = (self.ptr.as_ptr(), self.capacity, self.align);
+        mem::forget(self);
+        ret
+    }
+
+    #[allow(clippy::mem_forget)]
+    #[inline]
+    /// Consumes an allocation without freeing associated memory, returning its pointer and a
+    /// layout describing its capacity and alignment.
+    ///
+    /// This is equivalent to `Allocation::into_raw`, but bundles the length and alignment together
+    /// so that they cannot be transposed when the allocation is reconstructed via
+    /// `Allocation::from_raw_parts`. As with `Allocation::into_raw`, any bytes beyond the logical
+    /// length are zeroed, and the reconstructed allocation has a length equal to the capacity of
+    /// the original, and is never zeroed before being freed.
+    pub fn into_raw_parts(mut self) -> (NonNull<u8>, Layout) {
+        self.zero_spare_capacity();
+        let ret = (self.ptr, self.layout());
         mem::forget(self);
         ret
     }
 
     #[inline]
-    /// Resizes an existing allocation.
+    /// Zeroes any bytes between the length and the capacity of the allocation, which may not have
+    /// been initialized.
+    fn zero_spare_capacity(&mut self) {
+        unsafe {
+            ptr::write_bytes(self.as_mut_ptr().add(self.len), 0, self.capacity - self.len);
+        }
+    }
+
+    #[inline]
+    /// Consumes an allocation without freeing associated memory, returning a mutable slice of its
+    /// first `self.len()` bytes which lives for the rest of the program.
+    ///
+    /// This deliberately leaks the allocated block of memory, as with `Box::leak`, and so is only
+    /// appropriate for data that is needed until the program exits. If the allocation was created
+    /// via `Allocation::new_secure`, it is never zeroed.
+    pub fn leak(self) -> &'static mut [u8] {
+        let len = self.len;
+        let (ptr, _, _) = self.into_raw();
+        unsafe { slice::from_raw_parts_mut(ptr, len) }
+    }
+
+    #[cfg(feature = "alloc")]
+    /// Converts an allocation into a boxed byte slice of the same length and contents.
+    ///
+    /// A `Box<[u8]>` frees its memory via the global allocator with an alignment of one byte, so
+    /// ownership of the existing block of memory can only be transferred to the box if it was
+    /// allocated the same way: the global allocator must be the backing allocator (see the `heap`
+    /// module), the alignment must be one byte, the capacity must equal the length, and the
+    /// allocation must not have been created via `Allocation::new_secure`. Otherwise, the
+    /// contents are copied into a new block of memory allocated via the global allocator, and the
+    /// existing allocation is freed.
+    ///
+    /// On failure, returns an error and frees the existing allocation.
+    pub fn into_boxed_slice(self) -> Result<Box<[u8]>> {
+        if self.len == 0 {
+            return Ok(Box::default());
+        }
+        if cfg!(not(any(feature = "std", feature = "jemalloc", feature = "mimalloc"))) &&
+            self.align == 1 && self.len == self.capacity && !self.secure
+        {
+            let (ptr, len, _) = self.into_raw();
+            return Ok(unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(ptr, len)) });
+        }
+        unsafe {
+            let ptr = global::alloc(Layout::from_size_align_unchecked(self.len, 1));
+            if ptr.is_null() {
+                return Err(Error::NotEnoughMemory);
+            }
+            ptr::copy_nonoverlapping(self.as_ptr(), ptr, self.len);
+            Ok(Box::from_raw(ptr::slice_from_raw_parts_mut(ptr, self.len)))
+        }
+    }
+
+    #[inline]
+    /// Resizes an existing allocation, setting both its length and capacity to `new_len`, and
+    /// zeroing any bytes beyond the old length so that every byte within the new length is
+    /// initialized.
     ///
     /// On failure, returns an error without modifying the existing allocation.
+    ///
+    /// Allocations created via `Allocation::new_secure` are always moved to a new block of memory,
+    /// with the old block being zeroed before it is freed.
     pub fn resize(&mut self, new_len: usize) -> Result<()> {
+        let old_len = self.len;
+        self.reallocate(new_len).map(|_| unsafe { self.extend_zeroed(old_len, new_len) })
+    }
+
+    #[inline]
+    /// Resizes an existing allocation, setting both its length and capacity to `new_len`, and
+    /// zeroing any bytes beyond the old length.
+    ///
+    /// This is equivalent to `Allocation::resize`, which also zeroes any bytes beyond the old
+    /// length. On failure, returns an error without modifying the existing allocation.
+    pub fn resize_zeroed(&mut self, new_len: usize) -> Result<()> {
+        self.resize(new_len)
+    }
+
+    #[inline]
+    /// Sets the length of an allocation to `new_len`, zeroing any bytes between `old_len` and
+    /// `new_len`.
+    ///
+    /// The capacity of the allocation must be at least `new_len`.
+    unsafe fn extend_zeroed(&mut self, old_len: usize, new_len: usize) {
+        if new_len > old_len {
+            ptr::write_bytes(self.as_mut_ptr().add(old_len), 0, new_len - old_len);
+        }
+        self.len = new_len;
+    }
+
+    #[inline]
+    /// Resizes an existing allocation, preserving the contents it shares with the resized
+    /// allocation and zeroing any bytes beyond the old length.
+    ///
+    /// This is an alias for `Allocation::resize_zeroed`. On failure, returns an error without
+    /// modifying the existing allocation.
+    pub fn realloc_preserving(&mut self, new_len: usize) -> Result<()> {
+        self.resize_zeroed(new_len)
+    }
+
+    #[inline]
+    /// Reserves capacity for at least `additional` more bytes beyond the current length.
+    ///
+    /// If the current capacity is insufficient, the allocated block of memory grows to at least
+    /// double its previous capacity, so that repeated calls take amortized constant time. The
+    /// length of the allocation is left unchanged.
+    ///
+    /// Returns `Error::CapacityOverflow` if the required capacity would overflow. On failure,
+    /// returns an error without modifying the existing allocation.
+    pub fn reserve(&mut self, additional: usize) -> Result<()> {
+        self.len
+            .checked_add(additional)
+            .ok_or(Error::CapacityOverflow)
+            .and_then(
+                |required| if required <= self.capacity {
+                    Ok(())
+                } else {
+                    self.reallocate(cmp::max(self.capacity.saturating_mul(2), required))
+                }
+            )
+    }
+
+    #[inline]
+    /// Appends the contents of a byte slice to the end of the allocation, reserving more capacity
+    /// as described in `Allocation::reserve` if necessary.
+    ///
+    /// On failure, returns an error without modifying the existing allocation.
+    pub fn append(&mut self, src: &[u8]) -> Result<()> {
+        self.reserve(src.len()).map(
+            |_| unsafe {
+                ptr::copy_nonoverlapping(src.as_ptr(), self.as_mut_ptr().add(self.len), src.len());
+                self.len += src.len();
+            }
+        )
+    }
+
+    #[inline]
+    /// Appends the contents of a byte slice to the end of the allocation.
+    ///
+    /// This is an alias for `Allocation::append`, named after `Vec::extend_from_slice`.
+    ///
+    /// # Examples
+    ///
+    /// Code building a byte buffer via `Vec<u8>` can usually be migrated by replacing the
+    /// constructor, and handling the errors that `Vec` would otherwise abort on:
+    ///
+    /// ```
+    /// use safe_alloc::allocation::Allocation;
+    ///
+    /// # fn main() -> safe_alloc::result::Result<()> {
+    /// // let mut buf = Vec::with_capacity(16);
+    /// let mut buf = Allocation::with_capacity_zeroed(16, 1)?;
+    /// // buf.extend_from_slice(b"hello, ");
+    /// buf.extend_from_slice(b"hello, ")?;
+    /// buf.extend_from_slice(b"world")?;
+    /// assert_eq!(&buf[..], &b"hello, world"[..]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn extend_from_slice(&mut self, other: &[u8]) -> Result<()> {
+        self.append(other)
+    }
+
+    #[inline]
+    /// Shrinks the capacity of an allocation to match its length, freeing any reserved capacity.
+    ///
+    /// Does nothing if the capacity already equals the length. Returns `Error::ZeroLength` if the
+    /// length is zero. On failure, returns an error without modifying the existing allocation.
+    pub fn shrink_to_fit(&mut self) -> Result<()> {
+        if self.len == self.capacity {
+            Ok(())
+        } else {
+            let len = self.len;
+            self.reallocate(len)
+        }
+    }
+
+    #[inline]
+    /// Sets the length of an allocation without reallocating it.
+    ///
+    /// # Safety
+    ///
+    /// This is unsafe because the new length is not checked against the capacity of the
+    /// allocation, and any bytes beyond the current length are not initialized. Undefined behavior
+    /// will occur if `new_len` exceeds `self.capacity()`, or if the first `new_len` bytes of the
+    /// allocated block of memory have not all been initialized, for example via
+    /// `Allocation::with_capacity_zeroed`.
+    pub unsafe fn set_len(&mut self, new_len: usize) {
+        debug_assert!(new_len <= self.capacity);
+        self.len = new_len;
+    }
+
+    #[inline]
+    /// Shortens an allocation to `new_len` bytes without reallocating it, leaving its capacity
+    /// unchanged.
+    ///
+    /// Has no effect if `new_len` is greater than or equal to the current length. The discarded
+    /// bytes remain part of the allocated block of memory, and can be reused via
+    /// `Allocation::reserve` without a new allocation.
+    pub fn truncate(&mut self, new_len: usize) {
+        self.len = cmp::min(self.len, new_len);
+    }
+
+    #[inline]
+    /// Grows an existing allocation by the specified number of bytes.
+    ///
+    /// Returns `Error::CapacityOverflow` if the new length would overflow. On failure, returns an
+    /// error without modifying the existing allocation.
+    pub fn grow(&mut self, additional: usize) -> Result<()> {
+        self.len
+            .checked_add(additional)
+            .ok_or(Error::CapacityOverflow)
+            .and_then(|new_len| self.resize(new_len))
+    }
+
+    #[inline]
+    /// Shrinks an existing allocation to the specified length.
+    ///
+    /// Returns `Error::InvalidIndex` if the new length is greater than the current length. On
+    /// failure, returns an error without modifying the existing allocation.
+    pub fn shrink_to(&mut self, new_len: usize) -> Result<()> {
+        if new_len > self.len {
+            Err(Error::InvalidIndex)
+        } else {
+            self.resize(new_len)
+        }
+    }
+
+    #[inline]
+    /// Resizes an existing allocation without moving it, setting both its length and capacity to
+    /// `new_len`.
+    ///
+    /// Returns `Ok(true)` if the allocation was resized, or `Ok(false)` if the allocator could not
+    /// resize it without moving it, in which case the allocation is left unchanged and a moving
+    /// `Allocation::resize` may be used instead.
+    ///
+    /// Allocations created via `Allocation::new_secure` are never shrunk in place, since the bytes
+    /// being discarded could not be zeroed beforehand without losing them on failure.
+    ///
+    /// On failure, returns an error without modifying the existing allocation.
+    pub fn resize_in_place(&mut self, new_len: usize) -> Result<bool> {
+        if self.secure && new_len < self.capacity {
+            return Ok(false);
+        }
         unsafe {
-            heap::reallocate(self.as_mut_ptr(), self.len, new_len, self.align).map(
-                |ptr| {
-                    self.ptr = Unique::new(ptr);
-                    self.len = new_len;
-                    ()
+            let old_len = self.len;
+            heap::reallocate_inplace(self.as_mut_ptr(), self.capacity, new_len, self.align).inspect(
+                |&resized| if resized {
+                    self.capacity = new_len;
+                    self.extend_zeroed(old_len, new_len);
                 }
             )
         }
     }
 
     #[inline]
-    /// Resizes an existing allocation without moving it.
+    /// Resizes an existing allocation, setting both its length and capacity to `new_len`, without
+    /// moving it if possible.
+    ///
+    /// This first attempts `Allocation::resize_in_place`, and only falls back to a moving
+    /// `Allocation::resize` if the allocation could not be resized in place, so in the worst case
+    /// two resizes are attempted.
+    ///
+    /// On failure, returns an error without modifying the existing allocation.
+    pub fn resize_smart(&mut self, new_len: usize) -> Result<()> {
+        self.resize_in_place(new_len).and_then(
+            |resized| if resized {
+                Ok(())
+            } else {
+                self.resize(new_len)
+            }
+        )
+    }
+
+    /// Changes the alignment of an existing allocation.
+    ///
+    /// Memory must be freed using the same alignment it was allocated with, so unless the new
+    /// alignment is equal to the current one, this always moves the allocation to a new block of
+    /// memory with the same length and capacity, even if the existing block happens to satisfy the
+    /// new alignment.
+    ///
+    /// Returns `Error::BadAlignment` if the new alignment is not a power of two. On failure,
+    /// returns an error without modifying the existing allocation.
+    pub fn realign(&mut self, new_align: usize) -> Result<()> {
+        if new_align == self.align {
+            return Ok(());
+        }
+        unsafe { Allocation::new_uninit(self.capacity, new_align) }.map(
+            |mut new_alloc| {
+                unsafe {
+                    ptr::copy_nonoverlapping(self.as_ptr(), new_alloc.as_mut_ptr(), self.len);
+                }
+                new_alloc.len = self.len;
+                new_alloc.secure = self.secure;
+                mem::swap(self, &mut new_alloc);
+            }
+        )
+    }
+
+    /// Splits an allocation into two at the specified offset.
+    ///
+    /// This does not split the existing block of memory in place. Instead, two new allocations
+    /// are made with lengths `mid` and `self.len() - mid` respectively, the corresponding bytes are
+    /// copied into each, and the existing allocation is freed. Both new allocations share the
+    /// alignment of the existing allocation.
+    ///
+    /// Returns `Error::InvalidIndex` if `mid` is greater than the length of the allocation, or
+    /// `Error::ZeroLength` if `mid` is zero or equal to the length of the allocation, since one of
+    /// the halves would then be empty. On failure, including in either of these cases, the
+    /// existing allocation is consumed and freed, and neither new allocation is leaked.
+    ///
+    /// Note that this method shadows `<[u8]>::split_at`, which is otherwise reachable via `Deref`;
+    /// `alloc.split_at(mid)` therefore consumes and copies the allocation. To split the contents
+    /// without doing so, call `alloc.as_slice().split_at(mid)` instead.
+    pub fn split_at(self, mid: usize) -> Result<(Allocation, Allocation)> {
+        if mid > self.len {
+            return Err(Error::InvalidIndex);
+        }
+        if mid == 0 || mid == self.len {
+            return Err(Error::ZeroLength);
+        }
+        unsafe { Allocation::new_uninit(mid, self.align) }.and_then(
+            |mut head| {
+                unsafe { Allocation::new_uninit(self.len - mid, self.align) }.map(
+                    |mut tail| {
+                        unsafe {
+                            ptr::copy_nonoverlapping(self.as_ptr(), head.as_mut_ptr(), mid);
+                            ptr::copy_nonoverlapping(
+                                self.as_ptr().add(mid),
+                                tail.as_mut_ptr(),
+                                self.len - mid,
+                            );
+                        }
+                        head.secure = self.secure;
+                        tail.secure = self.secure;
+                        (head, tail)
+                    }
+                )
+            }
+        )
+    }
+
+    #[inline]
+    /// Borrows the bytes of an allocation as two disjoint mutable slices, divided at the specified
+    /// offset.
+    ///
+    /// Unlike `Allocation::split_at`, this neither consumes nor copies the allocation. The first
+    /// slice holds the bytes before `mid`, and the second holds the remaining bytes.
+    ///
+    /// Returns `Error::InvalidIndex` if `mid` is greater than the length of the allocation.
+    pub fn split_at_mut(&mut self, mid: usize) -> Result<(&mut [u8], &mut [u8])> {
+        self.check_range(0, mid)
+            .map(move |_| self.as_mut_slice().split_at_mut(mid))
+    }
+
+    /// Changes the capacity of an existing allocation, truncating its length if necessary.
+    ///
+    /// A zero-capacity allocation, such as one created via `Allocation::from_raw` with a length of
+    /// zero, does not own a block of memory, so a new one is allocated instead.
     ///
     /// On failure, returns an error without modifying the existing allocation.
-    pub fn resize_in_place(&mut self, new_len: usize) -> Result<()> {
+    fn reallocate(&mut self, new_capacity: usize) -> Result<()> {
+        if self.secure {
+            return self.reallocate_secure(new_capacity);
+        }
         unsafe {
-            heap::reallocate_inplace(self.as_mut_ptr(), self.len, new_len, self.align).map(
-                |len| {
-                    self.len = len;
-                    ()
+            if self.capacity == 0 {
+                heap::allocate(new_capacity, self.align)
+            } else {
+                heap::reallocate(self.as_mut_ptr(), self.capacity, new_capacity, self.align)
+            }.map(
+                |ptr| {
+                    self.ptr = NonNull::new_unchecked(ptr);
+                    self.len = cmp::min(self.len, new_capacity);
+                    self.capacity = new_capacity;
                 }
             )
         }
     }
 
+    /// Changes the capacity of an allocation created via `Allocation::new_secure` by moving it to
+    /// a new block of memory, zeroing the old block before freeing it.
+    fn reallocate_secure(&mut self, new_capacity: usize) -> Result<()> {
+        unsafe { Allocation::new_uninit(new_capacity, self.align) }.map(
+            |mut new_alloc| {
+                new_alloc.secure = true;
+                new_alloc.len = cmp::min(self.len, new_capacity);
+                unsafe {
+                    ptr::copy_nonoverlapping(
+                        self.as_ptr(),
+                        new_alloc.as_mut_ptr(),
+                        new_alloc.len,
+                    );
+                }
+                mem::swap(self, &mut new_alloc);
+            }
+        )
+    }
+
+    /// Overwrites a block of memory with zeros, using volatile writes so that the compiler cannot
+    /// elide them.
+    unsafe fn wipe(ptr: *mut u8, len: usize) {
+        for i in 0..len {
+            ptr::write_volatile(ptr.add(i), 0);
+        }
+    }
+
     /// Creates a new memory allocation with the same length, alignment and contents as an
     /// existing allocation.
+    ///
+    /// If the existing allocation was created via `Allocation::new_secure`, so is the new one. If
+    /// the existing allocation is empty, the new one is empty and has a capacity of zero, so no
+    /// memory is allocated.
+    ///
+    /// This is the fallible counterpart to `Clone::clone`, which panics on failure instead.
     pub fn duplicate(&self) -> Result<Allocation> {
-        Allocation::new(self.len, self.align).map(
+        unsafe { Allocation::new_uninit_or_empty(self.len, self.align) }.map(
             |mut new_alloc| {
                 unsafe {
-                    intrinsics::copy_nonoverlapping(
+                    ptr::copy_nonoverlapping(
                         self.as_ptr(),
                         new_alloc.as_mut_ptr(),
                         self.len,
                     );
                 }
+                new_alloc.secure = self.secure;
                 new_alloc
             }
         )
     }
 
-    /// Returns a raw pointer to the allocated block of memory.
-    pub fn as_ptr(&self) -> *const u8 {
-        unsafe { &*self.ptr.as_ptr() }
-    }
-
-    /// Returns a mutable raw pointer to the allocated block of memory.
-    pub fn as_mut_ptr(&mut self) -> *mut u8 {
-        self.ptr.as_ptr()
+    #[inline]
+    /// Creates a new memory allocation with the same length, alignment and contents as an
+    /// existing allocation.
+    ///
+    /// This is an alias for `Allocation::duplicate`, named after the fallible `try_clone` methods
+    /// found elsewhere, such as `std::fs::File::try_clone`.
+    pub fn try_clone(&self) -> Result<Allocation> {
+        self.duplicate()
     }
 
-    /// Returns the length in bytes of the allocated block of memory.
-    pub fn len(&self) -> usize {
-        self.len
+    #[inline]
+    /// Overwrites an existing allocation with the length and contents of another allocation,
+    /// reusing its block of memory where possible.
+    ///
+    /// The block of memory is only resized if its capacity is smaller than the length of `other`;
+    /// otherwise the contents are copied directly. The alignment of the existing allocation, and
+    /// whether it was created via `Allocation::new_secure`, are left unchanged.
+    ///
+    /// On failure, returns an error without modifying the existing allocation.
+    pub fn clone_from(&mut self, other: &Allocation) -> Result<()> {
+        if other.len > self.capacity {
+            self.resize(other.len)?;
+        }
+        self.len = other.len;
+        self.copy_from_slice(other.as_slice())
     }
 
-    /// Returns the alignment in bytes of the allocated block of memory.
-    pub fn align(&self) -> usize {
-        self.align
+    /// Creates a new memory allocation with the same length and alignment as an existing
+    /// allocation, holding the result of applying `f` to each of its bytes in turn.
+    ///
+    /// If the existing allocation was created via `Allocation::new_secure`, so is the new one.
+    pub fn map<F: FnMut(u8) -> u8>(&self, mut f: F) -> Result<Allocation> {
+        unsafe { Allocation::new_uninit_or_empty(self.len, self.align) }.map(
+            |mut new_alloc| {
+                let dst = new_alloc.as_mut_ptr();
+                for (i, &byte) in self.iter().enumerate() {
+                    unsafe {
+                        dst.add(i).write(f(byte));
+                    }
+                }
+                new_alloc.secure = self.secure;
+                new_alloc
+            }
+        )
     }
-}
 
-impl Drop for Allocation {
     #[inline]
-    fn drop(&mut self) {
+    /// Sets every byte of the allocated block of memory to the specified value.
+    pub fn fill(&mut self, byte: u8) {
         unsafe {
-            heap::deallocate(self.as_mut_ptr(), self.len, self.align);
+            ptr::write_bytes(self.as_mut_ptr(), byte, self.len);
         }
     }
-}
 
-impl fmt::Debug for Allocation {
+    #[inline]
+    /// Sets every byte within the specified range of offsets to the specified value.
+    ///
+    /// Returns `Error::InvalidIndex` without modifying the allocation if the range is decreasing
+    /// or extends past the end of the allocation.
+    pub fn fill_range(&mut self, range: Range<usize>, byte: u8) -> Result<()> {
+        range
+            .end
+            .checked_sub(range.start)
+            .ok_or(Error::InvalidIndex)
+            .and_then(|len| self.check_range(range.start, len).map(|_| len))
+            .map(
+                |len| unsafe {
+                    ptr::write_bytes(self.as_mut_ptr().add(range.start), byte, len);
+                }
+            )
+    }
+
+    #[inline]
+    /// Sets every byte of the allocated block of memory to zero.
+    pub fn zero(&mut self) {
+        unsafe {
+            ptr::write_bytes(self.as_mut_ptr(), 0, self.len);
+        }
+    }
+
+    #[inline]
+    /// Copies the contents of a slice into the start of the allocated block of memory.
+    ///
+    /// If the slice is shorter than the allocation, the remaining bytes are left untouched. If it
+    /// is longer, returns `Error::InvalidIndex` without modifying the allocation.
+    pub fn copy_from_slice(&mut self, src: &[u8]) -> Result<()> {
+        if src.len() > self.len {
+            Err(Error::InvalidIndex)
+        } else {
+            unsafe {
+                ptr::copy_nonoverlapping(src.as_ptr(), self.as_mut_ptr(), src.len());
+            }
+            Ok(())
+        }
+    }
+
+    #[inline]
+    /// Copies `count` bytes from offset `src` to offset `dst` within the allocation. The two
+    /// ranges may overlap.
+    ///
+    /// Returns `Error::InvalidIndex` without modifying the allocation if either range extends
+    /// past the end of the allocation.
+    pub fn memmove_within(&mut self, src: usize, dst: usize, count: usize) -> Result<()> {
+        self.check_range(src, count)
+            .and_then(|_| self.check_range(dst, count))
+            .map(
+                |_| unsafe {
+                    let ptr = self.as_mut_ptr();
+                    ptr::copy(ptr.add(src), ptr.add(dst), count);
+                }
+            )
+    }
+
+    #[inline]
+    /// Swaps the bytes at offsets `a` and `b`.
+    ///
+    /// Returns `Error::InvalidIndex` without modifying the allocation if either offset is out of
+    /// bounds.
+    pub fn swap_bytes(&mut self, a: usize, b: usize) -> Result<()> {
+        self.check_range(a, 1)
+            .and_then(|_| self.check_range(b, 1))
+            .map(|_| self.as_mut_slice().swap(a, b))
+    }
+
+    #[inline]
+    /// Rotates the bytes of the allocation in place so that the byte at offset `mid` becomes the
+    /// first byte.
+    ///
+    /// Returns `Error::InvalidIndex` without modifying the allocation if `mid` is greater than
+    /// the length of the allocation.
+    pub fn rotate_left(&mut self, mid: usize) -> Result<()> {
+        self.check_range(0, mid)
+            .map(|_| self.as_mut_slice().rotate_left(mid))
+    }
+
+    #[inline]
+    /// Rotates the bytes of the allocation in place so that the last `k` bytes come first.
+    ///
+    /// Returns `Error::InvalidIndex` without modifying the allocation if `k` is greater than the
+    /// length of the allocation.
+    pub fn rotate_right(&mut self, k: usize) -> Result<()> {
+        self.check_range(0, k)
+            .map(|_| self.as_mut_slice().rotate_right(k))
+    }
+
+    #[inline]
+    /// Reverses the order of the bytes of the allocation in place.
+    pub fn reverse(&mut self) {
+        self.as_mut_slice().reverse()
+    }
+
+    /// Collapses each run of equal adjacent bytes into a single byte in place, as with
+    /// `Vec::dedup`, returning the new length.
+    ///
+    /// The length of the allocation is updated accordingly, but its capacity is left unchanged.
+    pub fn dedup_adjacent(&mut self) -> usize {
+        let new_len = {
+            let bytes = self.as_mut_slice();
+            let mut written = cmp::min(bytes.len(), 1);
+            for read in 1..bytes.len() {
+                if bytes[read] != bytes[written - 1] {
+                    bytes[written] = bytes[read];
+                    written += 1;
+                }
+            }
+            written
+        };
+        self.len = new_len;
+        new_len
+    }
+
+    #[inline]
+    /// Reads `N` bytes starting at the specified offset into an array.
+    fn read_array<const N: usize>(&self, offset: usize) -> Result<[u8; N]> {
+        self.check_range(offset, N).map(
+            |_| {
+                let mut bytes = [0; N];
+                bytes.copy_from_slice(&self.as_slice()[offset..offset + N]);
+                bytes
+            }
+        )
+    }
+
+    #[inline]
+    /// Writes an array of `N` bytes starting at the specified offset.
+    fn write_array<const N: usize>(&mut self, offset: usize, bytes: [u8; N]) -> Result<()> {
+        self.check_range(offset, N)
+            .map(|_| self.as_mut_slice()[offset..offset + N].copy_from_slice(&bytes))
+    }
+
+    #[inline]
+    /// Reads a `u16` stored in little-endian byte order at the specified offset.
+    ///
+    /// Returns `Error::InvalidIndex` if the value would extend past the end of the allocation.
+    pub fn read_u16_le(&self, offset: usize) -> Result<u16> {
+        self.read_array(offset).map(u16::from_le_bytes)
+    }
+
+    #[inline]
+    /// Writes a `u16` in little-endian byte order at the specified offset.
+    ///
+    /// Returns `Error::InvalidIndex` without modifying the allocation if the value would extend
+    /// past the end of the allocation.
+    pub fn write_u16_le(&mut self, offset: usize, val: u16) -> Result<()> {
+        self.write_array(offset, val.to_le_bytes())
+    }
+
+    #[inline]
+    /// Reads a `u16` stored in big-endian byte order at the specified offset.
+    ///
+    /// Returns `Error::InvalidIndex` if the value would extend past the end of the allocation.
+    pub fn read_u16_be(&self, offset: usize) -> Result<u16> {
+        self.read_array(offset).map(u16::from_be_bytes)
+    }
+
+    #[inline]
+    /// Writes a `u16` in big-endian byte order at the specified offset.
+    ///
+    /// Returns `Error::InvalidIndex` without modifying the allocation if the value would extend
+    /// past the end of the allocation.
+    pub fn write_u16_be(&mut self, offset: usize, val: u16) -> Result<()> {
+        self.write_array(offset, val.to_be_bytes())
+    }
+
+    #[inline]
+    /// Reads a `u32` stored in little-endian byte order at the specified offset.
+    ///
+    /// Returns `Error::InvalidIndex` if the value would extend past the end of the allocation.
+    pub fn read_u32_le(&self, offset: usize) -> Result<u32> {
+        self.read_array(offset).map(u32::from_le_bytes)
+    }
+
+    #[inline]
+    /// Writes a `u32` in little-endian byte order at the specified offset.
+    ///
+    /// Returns `Error::InvalidIndex` without modifying the allocation if the value would extend
+    /// past the end of the allocation.
+    pub fn write_u32_le(&mut self, offset: usize, val: u32) -> Result<()> {
+        self.write_array(offset, val.to_le_bytes())
+    }
+
+    #[inline]
+    /// Reads a `u32` stored in big-endian byte order at the specified offset.
+    ///
+    /// Returns `Error::InvalidIndex` if the value would extend past the end of the allocation.
+    pub fn read_u32_be(&self, offset: usize) -> Result<u32> {
+        self.read_array(offset).map(u32::from_be_bytes)
+    }
+
+    #[inline]
+    /// Writes a `u32` in big-endian byte order at the specified offset.
+    ///
+    /// Returns `Error::InvalidIndex` without modifying the allocation if the value would extend
+    /// past the end of the allocation.
+    pub fn write_u32_be(&mut self, offset: usize, val: u32) -> Result<()> {
+        self.write_array(offset, val.to_be_bytes())
+    }
+
+    #[inline]
+    /// Reads a `u64` stored in little-endian byte order at the specified offset.
+    ///
+    /// Returns `Error::InvalidIndex` if the value would extend past the end of the allocation.
+    pub fn read_u64_le(&self, offset: usize) -> Result<u64> {
+        self.read_array(offset).map(u64::from_le_bytes)
+    }
+
+    #[inline]
+    /// Writes a `u64` in little-endian byte order at the specified offset.
+    ///
+    /// Returns `Error::InvalidIndex` without modifying the allocation if the value would extend
+    /// past the end of the allocation.
+    pub fn write_u64_le(&mut self, offset: usize, val: u64) -> Result<()> {
+        self.write_array(offset, val.to_le_bytes())
+    }
+
+    #[inline]
+    /// Reads a `u64` stored in big-endian byte order at the specified offset.
+    ///
+    /// Returns `Error::InvalidIndex` if the value would extend past the end of the allocation.
+    pub fn read_u64_be(&self, offset: usize) -> Result<u64> {
+        self.read_array(offset).map(u64::from_be_bytes)
+    }
+
+    #[inline]
+    /// Writes a `u64` in big-endian byte order at the specified offset.
+    ///
+    /// Returns `Error::InvalidIndex` without modifying the allocation if the value would extend
+    /// past the end of the allocation.
+    pub fn write_u64_be(&mut self, offset: usize, val: u64) -> Result<()> {
+        self.write_array(offset, val.to_be_bytes())
+    }
+
+    #[inline]
+    /// Returns the bit at the specified index, treating the allocation as a packed array of bits.
+    ///
+    /// Bit `index` is bit `index % 8` of byte `index / 8`, where bit 0 is the least significant.
+    ///
+    /// Returns `Error::InvalidIndex` if the index is not less than `self.len() * 8`.
+    pub fn get_bit(&self, index: usize) -> Result<bool> {
+        self.check_range(index / 8, 1)
+            .map(|_| self.as_slice()[index / 8] & (1 << (index % 8)) != 0)
+    }
+
+    #[inline]
+    /// Sets the bit at the specified index, treating the allocation as a packed array of bits, as
+    /// described in `Allocation::get_bit`.
+    ///
+    /// Returns `Error::InvalidIndex` without modifying the allocation if the index is not less than
+    /// `self.len() * 8`.
+    pub fn set_bit(&mut self, index: usize, value: bool) -> Result<()> {
+        self.check_range(index / 8, 1).map(
+            |_| {
+                let byte = &mut self.as_mut_slice()[index / 8];
+                let mask = 1 << (index % 8);
+                if value {
+                    *byte |= mask;
+                } else {
+                    *byte &= !mask;
+                }
+            }
+        )
+    }
+
+    #[inline]
+    /// Returns `true` if the allocation has the same length and contents as a byte slice.
+    pub fn eq_bytes(&self, other: &[u8]) -> bool {
+        self.len == other.len() && self.as_slice() == other
+    }
+
+    #[inline]
+    /// Compares the contents of two allocations lexicographically, as with byte slices.
+    ///
+    /// An allocation whose contents are a prefix of another's is ordered before it.
+    pub fn memcmp(&self, other: &Allocation) -> Ordering {
+        self.as_slice().cmp(other.as_slice())
+    }
+
+    /// Returns `true` if the allocation has the same length and contents as a byte slice, taking
+    /// time independent of the contents being compared.
+    ///
+    /// Unlike `Allocation::eq_bytes`, this does not stop at the first differing byte: every byte
+    /// is compared, and the differences accumulated, before the result is determined. This makes
+    /// it suitable for comparing secrets such as MACs or password hashes. The lengths are not
+    /// considered secret, so `false` is returned immediately if they differ.
+    ///
+    /// This is constant-time in the contents on a best-effort basis; the compiler is prevented
+    /// from short-circuiting the comparison, but no guarantee is made about the generated code.
+    pub fn ct_eq(&self, other: &[u8]) -> bool {
+        if self.len != other.len() {
+            return false;
+        }
+        let diff = self.iter().zip(other).fold(0, |acc, (a, b)| acc | (a ^ b));
+        hint::black_box(diff) == 0
+    }
+
+    #[inline]
+    /// Returns the offset of the first byte equal to the specified value, if any.
+    pub fn position_of(&self, byte: u8) -> Option<usize> {
+        self.iter().position(|&b| b == byte)
+    }
+
+    #[inline]
+    /// Returns the offset of the last byte equal to the specified value, if any.
+    pub fn rposition_of(&self, byte: u8) -> Option<usize> {
+        self.iter().rposition(|&b| b == byte)
+    }
+
+    /// Returns the offset of the first occurrence of a byte sequence within the allocation, if
+    /// any.
+    ///
+    /// An empty needle is found at offset zero, consistent with string searching.
+    pub fn find_subsequence(&self, needle: &[u8]) -> Option<usize> {
+        match needle.split_first() {
+            None => Some(0),
+            Some((&first, rest)) => {
+                let haystack = self.as_slice();
+                let last_start = haystack.len().checked_sub(needle.len())?;
+                (0..=last_start).find(
+                    |&i| haystack[i] == first && &haystack[i + 1..i + needle.len()] == rest
+                )
+            }
+        }
+    }
+
+    #[inline]
+    /// Appends a byte to the end of the allocation, reserving more capacity if necessary.
+    fn push(&mut self, byte: u8) -> Result<()> {
+        self.reserve(1).map(
+            |_| unsafe {
+                self.as_mut_ptr().add(self.len).write(byte);
+                self.len += 1;
+            }
+        )
+    }
+
+    #[inline]
+    /// Checks that the range of `len` bytes starting at `offset` lies within the allocation.
+    fn check_range(&self, offset: usize, len: usize) -> Result<()> {
+        if offset.checked_add(len).is_some_and(|end| end <= self.len) {
+            Ok(())
+        } else {
+            Err(Error::InvalidIndex)
+        }
+    }
+
+    /// Returns a raw pointer to the allocated block of memory.
+    ///
+    /// No reference to the block of memory is created in the process, so this never asserts the
+    /// validity of the memory behind the pointer.
+    pub fn as_ptr(&self) -> *const u8 {
+        self.ptr.as_ptr() as *const u8
+    }
+
+    /// Returns a mutable raw pointer to the allocated block of memory.
+    pub fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.ptr.as_ptr()
+    }
+
+    /// Returns the two raw pointers spanning the first `self.len()` bytes of the allocated block
+    /// of memory, in the manner of the slice method of the same name.
+    ///
+    /// The end pointer is one byte past the last byte of the allocation.
+    pub fn as_ptr_range(&self) -> Range<*const u8> {
+        let start = self.as_ptr();
+        start..start.wrapping_add(self.len)
+    }
+
+    /// Returns the two mutable raw pointers spanning the first `self.len()` bytes of the allocated
+    /// block of memory, in the manner of the slice method of the same name.
+    ///
+    /// The end pointer is one byte past the last byte of the allocation.
+    pub fn as_mut_ptr_range(&mut self) -> Range<*mut u8> {
+        let start = self.as_mut_ptr();
+        start..start.wrapping_add(self.len)
+    }
+
+    /// Returns a raw pointer to the byte at `offset`, after checking that the range of `len` bytes
+    /// starting there lies within the allocation.
+    ///
+    /// Returns `Error::InvalidIndex` if the range extends past the end of the allocation.
+    pub fn subslice_ptr(&self, offset: usize, len: usize) -> Result<*const u8> {
+        self.check_range(offset, len)
+            .map(|_| unsafe { self.as_ptr().add(offset) })
+    }
+
+    /// Returns a mutable raw pointer to the byte at `offset`, after checking that the range of
+    /// `len` bytes starting there lies within the allocation.
+    ///
+    /// Returns `Error::InvalidIndex` if the range extends past the end of the allocation.
+    pub fn subslice_ptr_mut(&mut self, offset: usize, len: usize) -> Result<*mut u8> {
+        self.check_range(offset, len)
+            .map(|_| unsafe { self.as_mut_ptr().add(offset) })
+    }
+
+    /// Returns a raw pointer to the first `self.len()` bytes of the allocated block of memory, as
+    /// a non-null slice pointer.
+    pub fn as_non_null_slice(&self) -> NonNull<[u8]> {
+        NonNull::slice_from_raw_parts(self.ptr, self.len)
+    }
+
+    /// Returns the first `self.len()` bytes of the allocated block of memory as a byte slice.
+    pub fn as_slice(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.as_ptr(), self.len) }
+    }
+
+    /// Returns the first `self.len()` bytes of the allocated block of memory as a mutable byte
+    /// slice.
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(self.as_mut_ptr(), self.len) }
+    }
+
+    #[inline]
+    /// Returns the first `len` bytes of the allocation as a byte slice, without bounds checking.
+    ///
+    /// This suits buffers which are filled incrementally, such as via `cursor::AllocationWriter`,
+    /// where only the filled prefix should be exposed. Every byte within the length of an
+    /// allocation is always initialized, so this is equivalent to `&self.as_slice()[..len]`
+    /// without the bounds check.
+    ///
+    /// # Safety
+    ///
+    /// `len` must not exceed the length of the allocation; this is checked in debug builds.
+    pub unsafe fn assume_init_slice(&self, len: usize) -> &[u8] {
+        debug_assert!(len <= self.len);
+        slice::from_raw_parts(self.as_ptr(), len)
+    }
+
+    /// Returns the allocation as a slice of `T`, with as many elements as fit in its length.
+    ///
+    /// # Safety
+    ///
+    /// This is unsafe because the allocation may not hold valid values of type `T`. Undefined
+    /// behavior will occur unless every element has been initialized. In addition, `T` must not be
+    /// zero-sized, the length of the allocation should be a multiple of the size of `T`, and the
+    /// allocated block of memory must be aligned for `T`; these are checked in debug builds.
+    pub unsafe fn as_typed_slice<T>(&self) -> &[T] {
+        debug_assert!(size_of::<T>() != 0);
+        debug_assert!(self.len.is_multiple_of(size_of::<T>()));
+        debug_assert!(self.aligned_offset(align_of::<T>()) == 0);
+        slice::from_raw_parts(self.as_ptr() as *const T, self.len / size_of::<T>())
+    }
+
+    /// Returns the allocation as a mutable slice of `T`, with as many elements as fit in its
+    /// length.
+    ///
+    /// # Safety
+    ///
+    /// This is unsafe for the same reasons as `Allocation::as_typed_slice`.
+    pub unsafe fn as_typed_slice_mut<T>(&mut self) -> &mut [T] {
+        debug_assert!(size_of::<T>() != 0);
+        debug_assert!(self.len.is_multiple_of(size_of::<T>()));
+        debug_assert!(self.aligned_offset(align_of::<T>()) == 0);
+        slice::from_raw_parts_mut(self.as_mut_ptr() as *mut T, self.len / size_of::<T>())
+    }
+
+    #[inline]
+    /// Returns the bytes of the allocation as a slice of `N`-byte arrays, followed by the
+    /// remaining bytes that do not fill a whole array, mirroring the slice method of the same
+    /// name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `N` is zero.
+    pub fn as_chunks<const N: usize>(&self) -> (&[[u8; N]], &[u8]) {
+        self.as_slice().as_chunks()
+    }
+
+    #[inline]
+    /// Returns the bytes of the allocation as a mutable slice of `N`-byte arrays, followed by the
+    /// remaining bytes that do not fill a whole array, mirroring the slice method of the same
+    /// name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `N` is zero.
+    pub fn as_chunks_mut<const N: usize>(&mut self) -> (&mut [[u8; N]], &mut [u8]) {
+        self.as_mut_slice().as_chunks_mut()
+    }
+
+    /// Returns an iterator over the bytes of the allocation.
+    pub fn iter(&self) -> slice::Iter<'_, u8> {
+        self.as_slice().iter()
+    }
+
+    /// Returns an iterator that allows modifying each byte of the allocation.
+    pub fn iter_mut(&mut self) -> slice::IterMut<'_, u8> {
+        self.as_mut_slice().iter_mut()
+    }
+
+    /// Returns an iterator over every contiguous window of `size` bytes in the allocation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero, as with the slice method of the same name.
+    pub fn windows(&self, size: usize) -> slice::Windows<'_, u8> {
+        self.as_slice().windows(size)
+    }
+
+    /// Returns an iterator over the bytes of the allocation in non-overlapping chunks of `size`
+    /// bytes, the last of which may be shorter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero, as with the slice method of the same name.
+    pub fn chunks(&self, size: usize) -> slice::Chunks<'_, u8> {
+        self.as_slice().chunks(size)
+    }
+
+    /// Returns an iterator over the bytes of the allocation in non-overlapping mutable chunks of
+    /// `size` bytes, the last of which may be shorter.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is zero, as with the slice method of the same name.
+    pub fn chunks_mut(&mut self, size: usize) -> slice::ChunksMut<'_, u8> {
+        self.as_mut_slice().chunks_mut(size)
+    }
+
+    /// Returns the length in bytes of the allocation.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the length of the allocation is zero.
+    ///
+    /// Although the constructors reject a length of zero, an allocation can still be empty, for
+    /// example if it was created via `Allocation::with_capacity_zeroed`, or if its length has
+    /// since been reduced via `Allocation::set_len`.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the length in bytes of the allocated block of memory, including any capacity
+    /// reserved beyond the length of the allocation.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the alignment in bytes of the allocated block of memory.
+    pub fn align(&self) -> usize {
+        self.align
+    }
+
+    /// Returns the number of bytes from the start of the allocated block of memory to the first
+    /// address that is a multiple of `align`.
+    ///
+    /// This is a byte offset, not a pointer, and may exceed the length of the allocation.
+    ///
+    /// `align` must be a power of two.
+    pub fn aligned_offset(&self, align: usize) -> usize {
+        debug_assert!(align.is_power_of_two());
+        (self.as_ptr() as usize).wrapping_neg() & (align - 1)
+    }
+
+    /// Returns `true` if the allocated block of memory is aligned to `align` bytes, which must be a
+    /// power of two; this is checked in debug builds.
+    ///
+    /// This checks the address itself, so it may return `true` for alignments larger than that of
+    /// the allocation.
+    pub fn is_aligned_to(&self, align: usize) -> bool {
+        debug_assert!(align.is_power_of_two());
+        (self.as_ptr() as usize) & (align - 1) == 0
+    }
+
+    /// Returns `true` if the alignment of the allocation is larger than the backing allocator is
+    /// assumed to honor, in which case it is aligned by this crate instead.
+    pub fn is_over_aligned(&self) -> bool {
+        self.align > heap::max_align()
+    }
+
+    /// Returns `true` if a pointer refers to one of the first `self.len()` bytes of the allocated
+    /// block of memory.
+    ///
+    /// This compares addresses only, and never dereferences or offsets the pointer.
+    pub fn contains_ptr(&self, ptr: *const u8) -> bool {
+        let start = self.as_ptr() as usize;
+        let addr = ptr as usize;
+        addr >= start && addr - start < self.len
+    }
+
+    #[inline]
+    /// Returns the length of the block of memory actually provided by the backing allocator,
+    /// which may exceed the capacity of the allocation. See `heap::usable_size` for details.
+    pub fn usable_len(&self) -> usize {
+        heap::usable_size(self.capacity, self.align)
+    }
+
+    /// Returns the length and alignment of the allocated block of memory as a layout.
+    pub fn layout(&self) -> Layout {
+        unsafe { Layout::from_size_align_unchecked(self.capacity, self.align) }
+    }
+}
+
+// An `Allocation` uniquely owns its block of memory, which is only ever accessed through it, much
+// like a `Box<[u8]>`. Shared references only permit reads, mutation requires `&mut self`, and the
+// backing allocator may free memory from any thread, so it is safe to send and share allocations
+// between threads.
+unsafe impl Send for Allocation {}
+unsafe impl Sync for Allocation {}
+
+impl Clone for Allocation {
+    #[inline]
+    /// Creates a new memory allocation with the same length, alignment and contents as an
+    /// existing allocation.
+    ///
+    /// This is a convenience wrapper around `Allocation::duplicate` that panics if the new
+    /// allocation cannot be made. Prefer `duplicate` wherever failure can be handled.
+    fn clone(&self) -> Allocation {
+        self.duplicate()
+            .unwrap_or_else(|e| panic!("failed to clone Allocation: {}", e))
+    }
+
+    #[inline]
+    /// Overwrites an existing allocation with the length and contents of another allocation,
+    /// reusing its block of memory where possible.
+    ///
+    /// This is a convenience wrapper around `Allocation::clone_from` that panics if the existing
+    /// allocation cannot be resized.
+    fn clone_from(&mut self, source: &Allocation) {
+        Allocation::clone_from(self, source)
+            .unwrap_or_else(|e| panic!("failed to clone Allocation: {}", e))
+    }
+}
+
+/// Two allocations are equal if they have the same length and contents. Their alignments and the
+/// addresses of their blocks of memory are not taken into account.
+impl PartialEq for Allocation {
+    #[inline]
+    fn eq(&self, other: &Allocation) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl Eq for Allocation {}
+
+/// Allocations are ordered lexicographically by their contents, consistent with their `PartialEq`
+/// impl and with the ordering of byte slices.
+impl PartialOrd for Allocation {
+    #[inline]
+    fn partial_cmp(&self, other: &Allocation) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Allocation {
+    #[inline]
+    fn cmp(&self, other: &Allocation) -> Ordering {
+        self.memcmp(other)
+    }
+}
+
+/// Hashes the length and contents of an allocation, consistent with its `PartialEq` impl.
+impl Hash for Allocation {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_slice().hash(state)
+    }
+}
+
+/// Frees the allocated block of memory, first zeroing it if the allocation was created via
+/// `Allocation::new_secure`. An allocation with a capacity of zero, such as one reconstructed via
+/// `Allocation::from_raw` with a length of zero, is treated as empty, and nothing is freed.
+impl Drop for Allocation {
+    #[inline]
+    fn drop(&mut self) {
+        if self.capacity == 0 {
+            return;
+        }
+        unsafe {
+            if self.secure {
+                Self::wipe(self.as_mut_ptr(), self.capacity);
+            }
+            heap::deallocate(self.as_mut_ptr(), self.capacity, self.align);
+        }
+    }
+}
+
+impl Deref for Allocation {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl DerefMut for Allocation {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut [u8] {
+        self.as_mut_slice()
+    }
+}
+
+/// Indexes into the bytes of an allocation, panicking if the index is out of bounds.
+impl<I: SliceIndex<[u8]>> Index<I> for Allocation {
+    type Output = I::Output;
+
+    #[inline]
+    fn index(&self, index: I) -> &I::Output {
+        &self.as_slice()[index]
+    }
+}
+
+/// Mutably indexes into the bytes of an allocation, panicking if the index is out of bounds.
+impl<I: SliceIndex<[u8]>> IndexMut<I> for Allocation {
+    #[inline]
+    fn index_mut(&mut self, index: I) -> &mut I::Output {
+        &mut self.as_mut_slice()[index]
+    }
+}
+
+impl<'a> IntoIterator for &'a Allocation {
+    type Item = &'a u8;
+    type IntoIter = slice::Iter<'a, u8>;
+
+    #[inline]
+    fn into_iter(self) -> slice::Iter<'a, u8> {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut Allocation {
+    type Item = &'a mut u8;
+    type IntoIter = slice::IterMut<'a, u8>;
+
+    #[inline]
+    fn into_iter(self) -> slice::IterMut<'a, u8> {
+        self.iter_mut()
+    }
+}
+
+impl AsRef<[u8]> for Allocation {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl AsMut<[u8]> for Allocation {
+    #[inline]
+    fn as_mut(&mut self) -> &mut [u8] {
+        self.as_mut_slice()
+    }
+}
+
+/// Borrows the bytes of an allocation, consistent with its `PartialEq` and `Hash` impls, so that
+/// an allocation used as a map key can be looked up via a byte slice.
+impl Borrow<[u8]> for Allocation {
+    #[inline]
+    fn borrow(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl BorrowMut<[u8]> for Allocation {
+    #[inline]
+    fn borrow_mut(&mut self) -> &mut [u8] {
+        self.as_mut_slice()
+    }
+}
+
+/// Allocates a block of memory using the length and alignment of a layout, as with
+/// `Allocation::from_layout`. Zero-sized layouts are rejected with `Error::ZeroLength`.
+impl TryFrom<Layout> for Allocation {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(layout: Layout) -> Result<Allocation> {
+        Allocation::from_layout(layout)
+    }
+}
+
+impl fmt::Debug for Allocation {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("Allocation")
             .field("ptr", &self.ptr.as_ptr())
             .field("len", &self.len)
+            .field("capacity", &self.capacity)
             .field("align", &self.align)
+            .field("secure", &self.secure)
+            .field(
+                "bytes",
+                &HexDump {
+                    bytes: self.as_slice(),
+                    redacted: self.secure,
+                },
+            )
             .finish()
     }
 }
+
+/// A truncated hexadecimal dump of the contents of an allocation, for use in its `Debug` impl.
+struct HexDump<'a> {
+    /// The contents of the allocation, all of which are initialized, and so can be safely read.
+    bytes: &'a [u8],
+    /// Whether the contents should be hidden, because the allocation may hold secrets.
+    redacted: bool,
+}
+
+impl<'a> HexDump<'a> {
+    /// The maximum number of bytes dumped by default.
+    const LIMIT: usize = 16;
+    /// The maximum number of bytes dumped when the alternate flag is used.
+    const ALTERNATE_LIMIT: usize = 256;
+}
+
+impl<'a> fmt::Debug for HexDump<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.redacted {
+            return f.write_str("<redacted>");
+        }
+        let limit = if f.alternate() {
+            Self::ALTERNATE_LIMIT
+        } else {
+            Self::LIMIT
+        };
+        for (i, b) in self.bytes.iter().take(limit).enumerate() {
+            if i != 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{:02x}", b)?;
+        }
+        if self.bytes.len() > limit {
+            f.write_str(" ...")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn as_slice_reads_back_writes_through_as_mut_slice() {
+        let mut alloc = Allocation::new(4, 1).unwrap();
+        alloc.as_mut_slice().copy_from_slice(&[1, 2, 3, 4]);
+        assert_eq!(alloc.as_slice(), &[1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn fill_sets_every_byte() {
+        let mut alloc = Allocation::new(32, 1).unwrap();
+        alloc.fill(0xAB);
+        assert!(alloc.as_slice().iter().all(|&b| b == 0xAB));
+    }
+
+    #[test]
+    fn zero_clears_every_byte() {
+        let mut alloc = Allocation::from_bytes(&[1, 2, 3, 4, 5], 1).unwrap();
+        alloc.zero();
+        assert!(alloc.as_slice().iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn equal_contents_hash_equally() {
+        use std::collections::hash_map::DefaultHasher;
+
+        fn hash_of(alloc: &Allocation) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            alloc.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let a = Allocation::from_bytes(b"hello", 1).unwrap();
+        let b = Allocation::from_bytes(b"hello", 16).unwrap();
+        assert_eq!(hash_of(&a), hash_of(&b));
+    }
+
+    #[test]
+    fn resize_zeroed_zeroes_grown_tail() {
+        let mut alloc = Allocation::new(4, 1).unwrap();
+        alloc.fill(0xFF);
+        alloc.resize_zeroed(12).unwrap();
+        assert_eq!(&alloc[..4], &[0xFF; 4]);
+        assert_eq!(&alloc[4..], &[0; 8]);
+    }
+
+    #[test]
+    fn memmove_within_handles_overlap_in_both_directions() {
+        let mut alloc = Allocation::from_bytes(&[1, 2, 3, 4, 5, 6], 1).unwrap();
+        alloc.memmove_within(0, 2, 4).unwrap();
+        assert_eq!(alloc.as_slice(), &[1, 2, 1, 2, 3, 4]);
+        alloc.memmove_within(2, 0, 4).unwrap();
+        assert_eq!(alloc.as_slice(), &[1, 2, 3, 4, 3, 4]);
+        assert_eq!(alloc.memmove_within(0, 3, 4), Err(Error::InvalidIndex));
+    }
+
+    #[test]
+    fn swap_bytes_handles_equal_and_boundary_indices() {
+        let mut alloc = Allocation::from_bytes(&[1, 2, 3, 4], 1).unwrap();
+        alloc.swap_bytes(2, 2).unwrap();
+        assert_eq!(alloc.as_slice(), &[1, 2, 3, 4]);
+        alloc.swap_bytes(0, 3).unwrap();
+        assert_eq!(alloc.as_slice(), &[4, 2, 3, 1]);
+        assert_eq!(alloc.swap_bytes(0, 4), Err(Error::InvalidIndex));
+        assert_eq!(alloc.as_slice(), &[4, 2, 3, 1]);
+    }
+
+    #[test]
+    fn with_capacity_zeroed_is_empty_but_zeroed() {
+        let mut alloc = Allocation::with_capacity_zeroed(16, 1).unwrap();
+        assert_eq!(alloc.len(), 0);
+        assert!(alloc.is_empty());
+        assert_eq!(alloc.capacity(), 16);
+        unsafe {
+            alloc.set_len(16);
+        }
+        assert_eq!(alloc.as_slice(), &[0; 16]);
+    }
+
+    #[test]
+    fn clone_from_reuses_block_of_matching_size() {
+        let mut dst = Allocation::new(8, 1).unwrap();
+        let src = Allocation::from_bytes(b"abcdefgh", 1).unwrap();
+        let ptr = dst.as_ptr();
+        dst.clone_from(&src).unwrap();
+        assert_eq!(dst.as_ptr(), ptr);
+        assert_eq!(dst, src);
+    }
+
+    #[test]
+    fn truncate_leaves_capacity_unchanged() {
+        let mut alloc = Allocation::from_bytes(&[1, 2, 3, 4, 5, 6], 1).unwrap();
+        alloc.truncate(2);
+        assert_eq!(alloc.as_slice(), &[1, 2]);
+        assert_eq!(alloc.capacity(), 6);
+        alloc.truncate(4);
+        assert_eq!(alloc.len(), 2);
+    }
+
+    #[test]
+    fn split_at_mut_yields_disjoint_halves() {
+        let mut alloc = Allocation::new(6, 1).unwrap();
+        {
+            let (head, tail) = alloc.split_at_mut(2).unwrap();
+            head.copy_from_slice(&[0xAA; 2]);
+            tail.copy_from_slice(&[0x55; 4]);
+        }
+        assert_eq!(alloc.as_slice(), &[0xAA, 0xAA, 0x55, 0x55, 0x55, 0x55]);
+        assert!(alloc.split_at_mut(7).is_err());
+    }
+
+    #[test]
+    fn append_concatenates_repeatedly() {
+        let mut alloc = Allocation::from_bytes(b"a", 1).unwrap();
+        for _ in 0..10 {
+            alloc.append(b"bc").unwrap();
+        }
+        assert_eq!(alloc.as_slice(), &b"abcbcbcbcbcbcbcbcbcbc"[..]);
+        assert!(alloc.capacity() >= alloc.len());
+    }
+
+    #[test]
+    fn zero_length_from_raw_is_dropped_safely() {
+        let alloc = unsafe { Allocation::from_raw(8 as *mut u8, 0, 1) };
+        assert!(alloc.is_empty());
+        assert_eq!(alloc.capacity(), 0);
+    }
+
+    #[test]
+    fn zero_length_from_raw_can_grow() {
+        let mut alloc = unsafe { Allocation::from_raw(8 as *mut u8, 0, 1) };
+        alloc.append(b"hello").unwrap();
+        assert_eq!(alloc.as_slice(), &b"hello"[..]);
+        let mut alloc = unsafe { Allocation::from_raw(8 as *mut u8, 0, 1) };
+        alloc.resize(4).unwrap();
+        assert_eq!(alloc.as_slice(), &[0; 4]);
+    }
+
+    #[test]
+    fn new_bounded_enforces_maximum() {
+        assert_eq!(Allocation::new_bounded(64, 1, 64).map(|a| a.len()), Ok(64));
+        assert_eq!(Allocation::new_bounded(65, 1, 64).err(), Some(Error::NotEnoughMemory));
+    }
+
+    #[test]
+    fn map_applies_function_to_each_byte() {
+        let alloc = Allocation::from_bytes(&[0x00, 0x0F, 0xF0, 0xFF], 2).unwrap();
+        let mapped = alloc.map(|b| !b).unwrap();
+        assert_eq!(mapped.as_slice(), &[0xFF, 0xF0, 0x0F, 0x00]);
+        assert_eq!(mapped.align(), 2);
+    }
+
+    #[test]
+    fn borrowed_slice_finds_owned_key() {
+        use std::collections::HashMap;
+
+        let mut map = HashMap::new();
+        let _ = map.insert(Allocation::from_bytes(b"key", 1).unwrap(), 42);
+        assert_eq!(map.get(&b"key"[..]), Some(&42));
+        assert_eq!(map.get(&b"other"[..]), None);
+    }
+
+    #[test]
+    fn realloc_preserving_keeps_shared_prefix() {
+        let mut alloc = Allocation::from_bytes(&[1, 2, 3, 4], 1).unwrap();
+        alloc.realloc_preserving(6).unwrap();
+        assert_eq!(alloc.as_slice(), &[1, 2, 3, 4, 0, 0]);
+        alloc.realloc_preserving(3).unwrap();
+        assert_eq!(alloc.as_slice(), &[1, 2, 3]);
+        assert_eq!(alloc.capacity(), 3);
+    }
+
+    #[test]
+    fn dedup_adjacent_all_equal() {
+        let mut alloc = Allocation::from_bytes(&[7; 5], 1).unwrap();
+        assert_eq!(alloc.dedup_adjacent(), 1);
+        assert_eq!(alloc.as_slice(), &[7]);
+        assert_eq!(alloc.capacity(), 5);
+    }
+
+    #[test]
+    fn dedup_adjacent_all_distinct() {
+        let mut alloc = Allocation::from_bytes(&[1, 2, 3, 4], 1).unwrap();
+        assert_eq!(alloc.dedup_adjacent(), 4);
+        assert_eq!(alloc.as_slice(), &[1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn dedup_adjacent_mixed_runs() {
+        let mut alloc = Allocation::from_bytes(&[1, 1, 2, 3, 3, 3, 1, 4, 4], 1).unwrap();
+        assert_eq!(alloc.dedup_adjacent(), 5);
+        assert_eq!(alloc.as_slice(), &[1, 2, 3, 1, 4]);
+    }
+
+    #[test]
+    fn debug_dumps_contents_of_fresh_allocation() {
+        use std::format;
+
+        let alloc = Allocation::new(2, 1).unwrap();
+        assert!(format!("{:?}", alloc).contains("bytes: 00 00"));
+    }
+
+    #[test]
+    fn into_raw_zeroes_spare_capacity() {
+        let mut alloc = Allocation::from_bytes(b"ab", 1).unwrap();
+        alloc.reserve(100).unwrap();
+        let (ptr, len, align) = alloc.into_raw();
+        let alloc = unsafe { Allocation::from_raw(ptr, len, align) };
+        assert_eq!(alloc.len(), len);
+        assert_eq!(&alloc[..2], &b"ab"[..]);
+        assert!(alloc[2..].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn raw_parts_round_trip_with_spare_capacity() {
+        let mut alloc = Allocation::with_capacity_zeroed(8, 4).unwrap();
+        alloc.append(b"abc").unwrap();
+        alloc.reserve(16).unwrap();
+        let capacity = alloc.capacity();
+        let (ptr, layout) = alloc.into_raw_parts();
+        assert_eq!((layout.size(), layout.align()), (capacity, 4));
+        let alloc = unsafe { Allocation::from_raw_parts(ptr, layout) };
+        assert_eq!(alloc.len(), capacity);
+        assert_eq!(&alloc[..3], &b"abc"[..]);
+        assert!(alloc[3..].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn empty_allocation_can_be_cloned() {
+        let mut alloc = Allocation::from_bytes(b"abc", 8).unwrap();
+        alloc.truncate(0);
+        let clone = alloc.clone();
+        assert!(clone.is_empty());
+        assert_eq!(clone.capacity(), 0);
+        assert_eq!(clone.align(), 8);
+        assert_eq!(clone.as_ptr() as usize % 8, 0);
+        assert!(alloc.map(|b| !b).unwrap().is_empty());
+        let mut clone = Allocation::with_capacity_zeroed(4, 1).unwrap().clone();
+        clone.append(b"xyz").unwrap();
+        assert_eq!(clone.as_slice(), &b"xyz"[..]);
+    }
+}
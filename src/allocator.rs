@@ -0,0 +1,127 @@
+// Copyright (c) 2017 FaultyRAM
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT
+// or http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! An adapter for using this crate's memory allocation APIs via the unstable `Allocator` trait.
+
+use core::cmp;
+use core::alloc::{AllocError, Allocator, Layout};
+use core::ptr::{self, NonNull};
+use core::result;
+use super::heap;
+use super::result::Result;
+
+#[derive(Clone, Copy, Debug, Default)]
+/// A memory allocator that implements `Allocator` on top of this crate's memory allocation APIs.
+///
+/// Zero-sized requests, which this crate otherwise rejects, are satisfied with a dangling pointer
+/// without calling into the backing allocator, as `Allocator` requires.
+pub struct SafeAllocator;
+
+#[inline]
+/// Returns a well-aligned, dangling pointer for a zero-sized allocation.
+fn dangling(layout: Layout) -> NonNull<[u8]> {
+    unsafe {
+        NonNull::slice_from_raw_parts(
+            NonNull::new_unchecked(ptr::without_provenance_mut(layout.align())),
+            0,
+        )
+    }
+}
+
+#[inline]
+/// Converts the result of a `heap` function into the form expected by `Allocator`.
+fn to_slice(result: Result<*mut u8>, len: usize) -> result::Result<NonNull<[u8]>, AllocError> {
+    result
+        .map(|ptr| unsafe { NonNull::slice_from_raw_parts(NonNull::new_unchecked(ptr), len) })
+        .map_err(|_| AllocError)
+}
+
+unsafe impl Allocator for SafeAllocator {
+    #[inline]
+    fn allocate(&self, layout: Layout) -> result::Result<NonNull<[u8]>, AllocError> {
+        if layout.size() == 0 {
+            Ok(dangling(layout))
+        } else {
+            to_slice(unsafe { heap::allocate(layout.size(), layout.align()) }, layout.size())
+        }
+    }
+
+    #[inline]
+    fn allocate_zeroed(&self, layout: Layout) -> result::Result<NonNull<[u8]>, AllocError> {
+        if layout.size() == 0 {
+            Ok(dangling(layout))
+        } else {
+            to_slice(
+                unsafe { heap::allocate_zeroed(layout.size(), layout.align()) },
+                layout.size(),
+            )
+        }
+    }
+
+    #[inline]
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        if layout.size() != 0 {
+            heap::deallocate(ptr.as_ptr(), layout.size(), layout.align())
+        }
+    }
+
+    #[inline]
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> result::Result<NonNull<[u8]>, AllocError> {
+        self.resize(ptr, old_layout, new_layout)
+    }
+
+    #[inline]
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> result::Result<NonNull<[u8]>, AllocError> {
+        self.resize(ptr, old_layout, new_layout)
+    }
+}
+
+impl SafeAllocator {
+    #[inline]
+    /// Resizes an existing allocation, reallocating it in place where the alignment is unchanged
+    /// and otherwise moving it to a new block of memory.
+    unsafe fn resize(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> result::Result<NonNull<[u8]>, AllocError> {
+        if old_layout.size() != 0 && new_layout.size() != 0 &&
+            old_layout.align() == new_layout.align()
+        {
+            return to_slice(
+                heap::reallocate(
+                    ptr.as_ptr(),
+                    old_layout.size(),
+                    new_layout.size(),
+                    new_layout.align(),
+                ),
+                new_layout.size(),
+            );
+        }
+        self.allocate(new_layout).inspect(
+            |new_ptr| {
+                ptr::copy_nonoverlapping(
+                    ptr.as_ptr(),
+                    new_ptr.as_ptr() as *mut u8,
+                    cmp::min(old_layout.size(), new_layout.size()),
+                );
+                self.deallocate(ptr, old_layout);
+            }
+        )
+    }
+}
@@ -0,0 +1,372 @@
+// Copyright (c) 2017 FaultyRAM
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT
+// or http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Bump allocators which hand out regions of memory and free them all at once.
+
+use core::{cmp, fmt, ptr, slice};
+use core::cell::Cell;
+use core::mem::size_of;
+use core::ptr::NonNull;
+use super::allocation::Allocation;
+use super::error::Error;
+use super::heap;
+use super::result::Result;
+
+#[inline]
+/// Computes the start and end offsets of a region of `len` bytes aligned to `align`, handed out
+/// from a block of memory at `base` holding `capacity` bytes, of which `used` are already in use.
+///
+/// Returns `None` if `align` is not a power of two, or if the region would not fit.
+fn bump(
+    base: *mut u8,
+    capacity: usize,
+    used: usize,
+    len: usize,
+    align: usize,
+) -> Option<(usize, usize)> {
+    if !align.is_power_of_two() {
+        return None;
+    }
+    let base = base as usize;
+    (base + used)
+        .checked_add(align - 1)
+        .map(|addr| (addr & !(align - 1)) - base)
+        .and_then(|start| start.checked_add(len).map(|end| (start, end)))
+        .filter(|&(_, end)| end <= capacity)
+}
+
+/// A bump allocator over a single, fixed-size allocation.
+///
+/// Regions are handed out in order from the start of the allocation, and are only freed all at
+/// once, either via `Arena::reset` or when the arena is dropped. The arena never grows.
+pub struct Arena {
+    /// The allocation regions are handed out from.
+    inner: Allocation,
+    /// A pointer to the start of the allocation, through which regions are handed out.
+    base: NonNull<u8>,
+    /// The offset of the first byte not yet handed out.
+    used: Cell<usize>,
+}
+
+impl Arena {
+    #[inline]
+    /// Creates an arena holding the specified number of bytes.
+    ///
+    /// The underlying allocation is aligned to `heap::max_align()`, and initially zeroed.
+    pub fn new(capacity: usize) -> Result<Arena> {
+        Allocation::zeroed(capacity, heap::max_align()).map(
+            |mut inner| {
+                let base = unsafe { NonNull::new_unchecked(inner.as_mut_ptr()) };
+                Arena {
+                    inner,
+                    base,
+                    used: Cell::new(0),
+                }
+            }
+        )
+    }
+
+    #[allow(clippy::mut_from_ref)]
+    /// Hands out a region of `len` bytes aligned to `align`.
+    ///
+    /// The region holds zeros if it has not been handed out before, or otherwise whatever was
+    /// written to it before the arena was last reset.
+    ///
+    /// Returns `None` if `align` is not a power of two, or if the arena does not have enough
+    /// space left for the region.
+    pub fn alloc_bytes(&self, len: usize, align: usize) -> Option<&mut [u8]> {
+        bump(self.base.as_ptr(), self.inner.len(), self.used.get(), len, align).map(
+            |(start, end)| {
+                self.used.set(end);
+                unsafe { slice::from_raw_parts_mut(self.base.as_ptr().add(start), len) }
+            }
+        )
+    }
+
+    #[inline]
+    /// Frees every region handed out so far, making the whole arena available again.
+    pub fn reset(&mut self) {
+        self.used.set(0);
+    }
+
+    #[inline]
+    /// Returns the total number of bytes the arena holds.
+    pub fn capacity(&self) -> usize {
+        self.inner.len()
+    }
+
+    #[inline]
+    /// Returns the number of bytes handed out so far, including padding for alignment.
+    pub fn used(&self) -> usize {
+        self.used.get()
+    }
+
+    #[inline]
+    /// Returns the number of bytes not yet handed out.
+    pub fn remaining(&self) -> usize {
+        self.capacity() - self.used()
+    }
+}
+
+// Regions handed out by an `Arena` borrow it, so they can never outlive it or be used on another
+// thread while the arena is moved there. The pointer is only an alias of the owned allocation.
+unsafe impl Send for Arena {}
+
+impl fmt::Debug for Arena {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Arena")
+            .field("capacity", &self.capacity())
+            .field("used", &self.used())
+            .finish()
+    }
+}
+
+/// The header stored at the start of each chunk of a `GrowableArena`, holding the previous chunk.
+type ChunkHeader = Option<Allocation>;
+
+/// A bump allocator which grows by chaining together multiple allocations.
+///
+/// Regions are handed out in order from the current chunk. When it is exhausted, a new chunk at
+/// least twice as large is allocated, and the old chunks are kept alive, so that every region
+/// handed out remains valid. All chunks but the current one are freed via `GrowableArena::reset`,
+/// and all of them when the arena is dropped.
+pub struct GrowableArena {
+    /// The current chunk, whose header holds the previous chunk, and so on.
+    chunk: Cell<Option<Allocation>>,
+    /// A pointer to the start of the current chunk.
+    base: Cell<NonNull<u8>>,
+    /// The length in bytes of the current chunk.
+    capacity: Cell<usize>,
+    /// The offset within the current chunk of the first byte not yet handed out.
+    used: Cell<usize>,
+    /// The total length in bytes of every chunk.
+    allocated: Cell<usize>,
+}
+
+impl GrowableArena {
+    #[inline]
+    /// Creates an arena whose first chunk has room for at least the specified number of bytes.
+    ///
+    /// Each chunk is aligned to `heap::max_align()`.
+    pub fn new(capacity: usize) -> Result<GrowableArena> {
+        Self::new_chunk(capacity, None).map(
+            |(mut chunk, capacity)| {
+                let base = unsafe { NonNull::new_unchecked(chunk.as_mut_ptr()) };
+                GrowableArena {
+                    chunk: Cell::new(Some(chunk)),
+                    base: Cell::new(base),
+                    capacity: Cell::new(capacity),
+                    used: Cell::new(size_of::<ChunkHeader>()),
+                    allocated: Cell::new(capacity),
+                }
+            }
+        )
+    }
+
+    /// Allocates a chunk with room for at least `len` bytes after its header, storing `prev` in
+    /// the header. Returns the chunk and its total length.
+    fn new_chunk(len: usize, prev: ChunkHeader) -> Result<(Allocation, usize)> {
+        len.checked_add(size_of::<ChunkHeader>())
+            .ok_or(Error::CapacityOverflow)
+            .and_then(
+                |capacity| {
+                    Allocation::zeroed(capacity, heap::max_align()).map(|chunk| (chunk, capacity))
+                }
+            )
+            .map(
+                |(mut chunk, capacity)| {
+                    unsafe {
+                        ptr::write(chunk.as_mut_ptr() as *mut ChunkHeader, prev);
+                    }
+                    (chunk, capacity)
+                }
+            )
+    }
+
+    #[allow(clippy::mut_from_ref)]
+    /// Hands out a region of `len` bytes aligned to `align`, allocating a new chunk if the current
+    /// one does not have enough space left.
+    ///
+    /// As with `Arena::alloc_bytes`, the region holds zeros if it has not been handed out before,
+    /// or otherwise whatever was written to it before the arena was last reset.
+    ///
+    /// Returns `Error::BadAlignment` if `align` is not a power of two, or
+    /// `Error::CapacityOverflow` if the size of the new chunk would overflow. On failure, no
+    /// region is handed out and the arena is left unchanged.
+    pub fn alloc_bytes(&self, len: usize, align: usize) -> Result<&mut [u8]> {
+        if !align.is_power_of_two() {
+            return Err(Error::BadAlignment);
+        }
+        let base = self.base.get().as_ptr();
+        let region = bump(base, self.capacity.get(), self.used.get(), len, align);
+        let (base, (start, end)) = match region {
+            Some(region) => (base, region),
+            None => self.grow(len, align)?,
+        };
+        self.used.set(end);
+        Ok(unsafe { slice::from_raw_parts_mut(base.add(start), len) })
+    }
+
+    /// Allocates a new chunk large enough for a region of `len` bytes aligned to `align`, and
+    /// makes it the current chunk. Returns a pointer to it and the offsets of the region within it.
+    fn grow(&self, len: usize, align: usize) -> Result<(*mut u8, (usize, usize))> {
+        len.checked_add(align)
+            .map(|required| cmp::max(self.capacity.get().saturating_mul(2), required))
+            .ok_or(Error::CapacityOverflow)
+            .and_then(|len| Self::new_chunk(len, None))
+            .and_then(
+                |(mut chunk, capacity)| {
+                    let base = chunk.as_mut_ptr();
+                    unsafe {
+                        ptr::write(base as *mut ChunkHeader, self.chunk.take());
+                    }
+                    self.chunk.set(Some(chunk));
+                    self.base.set(unsafe { NonNull::new_unchecked(base) });
+                    self.capacity.set(capacity);
+                    self.allocated.set(self.allocated.get() + capacity);
+                    bump(base, capacity, size_of::<ChunkHeader>(), len, align)
+                        .map(|region| (base, region))
+                        .ok_or(Error::CapacityOverflow)
+                }
+            )
+    }
+
+    /// Frees every region handed out so far, keeping only the current chunk, which is made
+    /// available again in its entirety.
+    pub fn reset(&mut self) {
+        if let Some(chunk) = self.chunk.get_mut().as_mut() {
+            unsafe {
+                let header = chunk.as_mut_ptr() as *mut ChunkHeader;
+                free_chunks(ptr::replace(header, None));
+            }
+        }
+        self.used.set(size_of::<ChunkHeader>());
+        self.allocated.set(self.capacity.get());
+    }
+
+    #[inline]
+    /// Returns the total number of bytes allocated for all chunks currently held by the arena.
+    pub fn allocated_bytes(&self) -> usize {
+        self.allocated.get()
+    }
+}
+
+/// Frees a chain of chunks, starting with `next` and following the header of each chunk.
+unsafe fn free_chunks(mut next: ChunkHeader) {
+    while let Some(mut chunk) = next {
+        next = ptr::read(chunk.as_mut_ptr() as *const ChunkHeader);
+    }
+}
+
+impl Drop for GrowableArena {
+    fn drop(&mut self) {
+        unsafe { free_chunks(self.chunk.take()) }
+    }
+}
+
+// As with `Arena`, regions handed out by a `GrowableArena` borrow it, and its pointer is only an
+// alias of the owned current chunk.
+unsafe impl Send for GrowableArena {}
+
+impl fmt::Debug for GrowableArena {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("GrowableArena")
+            .field("capacity", &self.capacity.get())
+            .field("used", &self.used.get())
+            .field("allocated", &self.allocated.get())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arena_counts_alignment_padding() {
+        let arena = Arena::new(64).unwrap();
+        assert_eq!(arena.alloc_bytes(1, 1).map(|r| r.len()), Some(1));
+        assert_eq!(arena.used(), 1);
+        let region = arena.alloc_bytes(4, 4).unwrap();
+        assert_eq!(region.as_ptr() as usize % 4, 0);
+        assert_eq!(arena.used(), 8);
+        assert_eq!(arena.remaining(), 56);
+    }
+
+    #[test]
+    fn arena_rejects_exhaustion_and_bad_alignment() {
+        let arena = Arena::new(64).unwrap();
+        assert!(arena.alloc_bytes(8, 3).is_none());
+        assert!(arena.alloc_bytes(65, 1).is_none());
+        assert_eq!(arena.used(), 0);
+        assert!(arena.alloc_bytes(64, 1).is_some());
+        assert!(arena.alloc_bytes(1, 1).is_none());
+        assert_eq!(arena.remaining(), 0);
+    }
+
+    #[test]
+    fn arena_reset_frees_whole_capacity() {
+        let mut arena = Arena::new(64).unwrap();
+        arena.alloc_bytes(48, 1).unwrap().fill(0xAB);
+        arena.reset();
+        assert_eq!(arena.used(), 0);
+        assert_eq!(arena.remaining(), arena.capacity());
+        let region = arena.alloc_bytes(64, 1).unwrap();
+        assert_eq!(&region[..48], &[0xAB; 48][..]);
+        assert_eq!(&region[48..], &[0; 16][..]);
+    }
+
+    #[test]
+    fn growable_arena_keeps_earlier_regions_valid() {
+        let header = size_of::<ChunkHeader>();
+        let arena = GrowableArena::new(64).unwrap();
+        assert_eq!(arena.allocated_bytes(), 64 + header);
+        let first = arena.alloc_bytes(48, 1).unwrap();
+        first.fill(0x11);
+        let second = arena.alloc_bytes(100, 1).unwrap();
+        second.fill(0x22);
+        assert_eq!(arena.allocated_bytes(), (64 + header) + (2 * (64 + header) + header));
+        assert!(first.iter().all(|&b| b == 0x11));
+        assert!(second.iter().all(|&b| b == 0x22));
+    }
+
+    #[test]
+    fn growable_arena_reset_keeps_only_current_chunk() {
+        let header = size_of::<ChunkHeader>();
+        let mut arena = GrowableArena::new(64).unwrap();
+        let second = 2 * (64 + header) + header;
+        let current = 2 * second + header;
+        for &len in &[64, 2 * (64 + header) - 1, 2 * second - 1] {
+            let _ = arena.alloc_bytes(len, 1).unwrap();
+        }
+        assert_eq!(arena.allocated_bytes(), (64 + header) + second + current);
+        arena.reset();
+        assert_eq!(arena.allocated_bytes(), current);
+        let _ = arena.alloc_bytes(current - header, 1).unwrap();
+        assert_eq!(arena.allocated_bytes(), current);
+    }
+
+    #[test]
+    fn growable_arena_handles_large_alignment() {
+        let align = heap::max_align() * 256;
+        let arena = GrowableArena::new(64).unwrap();
+        let region = arena.alloc_bytes(16, align).unwrap();
+        assert_eq!(region.len(), 16);
+        assert_eq!(region.as_ptr() as usize % align, 0);
+        region.fill(0x33);
+        let region = arena.alloc_bytes(16, align).unwrap();
+        assert_eq!(region.as_ptr() as usize % align, 0);
+    }
+
+    #[test]
+    fn growable_arena_rejects_bad_alignment() {
+        let arena = GrowableArena::new(64).unwrap();
+        let allocated = arena.allocated_bytes();
+        assert_eq!(arena.alloc_bytes(8, 3).err(), Some(Error::BadAlignment));
+        assert_eq!(arena.allocated_bytes(), allocated);
+    }
+}
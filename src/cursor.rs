@@ -0,0 +1,135 @@
+// Copyright (c) 2017 FaultyRAM
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT
+// or http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Cursors for sequentially accessing the contents of an allocation.
+//!
+//! These do not depend on `std::io`, and so are available in `no_std` builds.
+
+use core::cmp;
+use super::allocation::Allocation;
+use super::error::Error;
+use super::result::Result;
+
+#[derive(Debug)]
+/// A cursor which writes sequentially into an allocation, tracking its position.
+pub struct AllocationWriter<'a> {
+    /// The allocation being written to.
+    inner: &'a mut Allocation,
+    /// The offset of the next byte to be written.
+    pos: usize,
+}
+
+impl<'a> AllocationWriter<'a> {
+    #[inline]
+    /// Creates a writer positioned at the start of an allocation.
+    pub fn new(inner: &'a mut Allocation) -> AllocationWriter<'a> {
+        AllocationWriter { inner, pos: 0 }
+    }
+
+    #[inline]
+    /// Copies a slice into the allocation at the current position, and advances past it.
+    ///
+    /// Returns `Error::InvalidIndex` without writing anything if the slice is longer than the
+    /// number of bytes remaining.
+    pub fn write_bytes(&mut self, src: &[u8]) -> Result<()> {
+        if src.len() > self.remaining() {
+            Err(Error::InvalidIndex)
+        } else {
+            let end = self.pos + src.len();
+            self.inner[self.pos..end].copy_from_slice(src);
+            self.pos = end;
+            Ok(())
+        }
+    }
+
+    #[inline]
+    /// Returns the offset of the next byte to be written.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    #[inline]
+    /// Returns the number of bytes between the current position and the end of the allocation.
+    pub fn remaining(&self) -> usize {
+        self.inner.len() - self.pos
+    }
+}
+
+#[derive(Clone, Debug)]
+/// A cursor which reads sequentially from an allocation, tracking its position.
+pub struct AllocationReader<'a> {
+    /// The allocation being read from.
+    inner: &'a Allocation,
+    /// The offset of the next byte to be read.
+    pos: usize,
+}
+
+impl<'a> AllocationReader<'a> {
+    #[inline]
+    /// Creates a reader positioned at the start of an allocation.
+    pub fn new(inner: &'a Allocation) -> AllocationReader<'a> {
+        AllocationReader { inner, pos: 0 }
+    }
+
+    #[inline]
+    /// Copies as many bytes as are available, up to the length of `dst`, from the current
+    /// position into `dst`, and advances past them.
+    ///
+    /// Returns the number of bytes read, which is zero once the end of the allocation is reached.
+    pub fn read_bytes(&mut self, dst: &mut [u8]) -> Result<usize> {
+        let count = cmp::min(dst.len(), self.remaining());
+        let end = self.pos + count;
+        dst[..count].copy_from_slice(&self.inner[self.pos..end]);
+        self.pos = end;
+        Ok(count)
+    }
+
+    #[inline]
+    /// Fills `dst` with bytes from the current position, and advances past them.
+    ///
+    /// Returns `Error::InvalidIndex` without reading anything if fewer than `dst.len()` bytes
+    /// remain.
+    pub fn read_exact(&mut self, dst: &mut [u8]) -> Result<()> {
+        if dst.len() > self.remaining() {
+            Err(Error::InvalidIndex)
+        } else {
+            self.read_bytes(dst).map(|_| ())
+        }
+    }
+
+    #[inline]
+    /// Returns the offset of the next byte to be read.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    #[inline]
+    /// Returns the number of bytes between the current position and the end of the allocation.
+    pub fn remaining(&self) -> usize {
+        self.inner.len() - self.pos
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reader_handles_partial_reads_at_tail() {
+        let alloc = Allocation::from_bytes(&[1, 2, 3, 4, 5], 1).unwrap();
+        let mut reader = AllocationReader::new(&alloc);
+        let mut buf = [0; 3];
+        assert_eq!(reader.read_bytes(&mut buf), Ok(3));
+        assert_eq!(buf, [1, 2, 3]);
+        assert_eq!(reader.read_exact(&mut buf), Err(Error::InvalidIndex));
+        assert_eq!(reader.position(), 3);
+        assert_eq!(reader.read_bytes(&mut buf), Ok(2));
+        assert_eq!(&buf[..2], &[4, 5]);
+        assert_eq!(reader.read_bytes(&mut buf), Ok(0));
+        assert_eq!(reader.remaining(), 0);
+    }
+}
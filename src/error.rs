@@ -7,25 +7,49 @@
 
 //! Errors in memory management, such as out of memory or bad alignment.
 
+use core::alloc::LayoutError;
 use core::fmt::{Display, Formatter, Result};
+#[cfg(feature = "std")]
+use std::error::Error as StdError;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 /// A memory management error.
 pub enum Error {
     /// There is not enough free memory to satisfy a memory (re)allocation.
     NotEnoughMemory,
+    /// The length of a requested memory (re)allocation overflowed while being computed.
+    CapacityOverflow,
     /// Attempted to perform a zero-length allocation.
     ZeroLength,
     /// An invalid alignment was passed to a memory management function.
     BadAlignment,
+    /// An index, offset or range passed to a bounds-checked operation lay outside the allocation.
+    InvalidIndex,
+    /// A `Layout` could not be constructed from a length and alignment.
+    BadLayout,
 }
 
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter) -> Result {
         match *self {
             Error::NotEnoughMemory => f.write_str("out of memory"),
+            Error::CapacityOverflow => f.write_str("allocation size overflowed"),
             Error::ZeroLength => f.write_str("allocation length must be greater than zero bytes"),
             Error::BadAlignment => f.write_str("alignment must be a power of two"),
+            Error::InvalidIndex => f.write_str("index out of bounds of allocation"),
+            Error::BadLayout => f.write_str("invalid memory layout"),
         }
     }
 }
+
+#[cfg(feature = "std")]
+impl StdError for Error {}
+
+/// Converts a failure to construct a `Layout` into `Error::BadLayout`, so that `?` can be used on
+/// `Layout`-returning functions wherever this crate's `Result` type is returned.
+impl From<LayoutError> for Error {
+    #[inline]
+    fn from(_: LayoutError) -> Error {
+        Error::BadLayout
+    }
+}
@@ -0,0 +1,46 @@
+// Copyright (c) 2017 FaultyRAM
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT
+// or http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! An adapter for using this crate's memory allocation APIs via `GlobalAlloc`.
+
+use core::alloc::{GlobalAlloc, Layout};
+use core::ptr;
+use super::heap;
+
+#[derive(Clone, Copy, Debug, Default)]
+/// A memory allocator that implements `GlobalAlloc` on top of this crate's memory allocation
+/// APIs.
+///
+/// Failed (re)allocations return a null pointer, as `GlobalAlloc` requires, rather than aborting.
+///
+/// Without the `std`, `jemalloc` or `mimalloc` feature, this crate allocates memory via the global
+/// allocator, so registering `SafeAlloc` as the global allocator would recurse infinitely. The
+/// `global_alloc` feature therefore implies `std`, so that memory is allocated via the system
+/// allocator unless jemalloc or mimalloc is selected instead.
+pub struct SafeAlloc;
+
+unsafe impl GlobalAlloc for SafeAlloc {
+    #[inline]
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        heap::allocate(layout.size(), layout.align()).unwrap_or(ptr::null_mut())
+    }
+
+    #[inline]
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        heap::allocate_zeroed(layout.size(), layout.align()).unwrap_or(ptr::null_mut())
+    }
+
+    #[inline]
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        heap::deallocate(ptr, layout.size(), layout.align())
+    }
+
+    #[inline]
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        heap::reallocate(ptr, layout.size(), new_size, layout.align()).unwrap_or(ptr::null_mut())
+    }
+}
@@ -6,26 +6,341 @@
 // copied, modified, or distributed except according to those terms.
 
 //! Low-level memory allocation APIs.
+//!
+//! These are thin wrappers around a backing allocator which check their arguments and return
+//! values rather than aborting on failure. The backing allocator is selected at compile time:
+//!
+//! * If the `jemalloc` feature is enabled, jemalloc is called directly via its `mallocx` API.
+//! * Otherwise, if the `mimalloc` feature is enabled, mimalloc is called directly via its aligned
+//!   allocation API.
+//! * Otherwise, if the `std` feature is enabled, the system allocator, `std::alloc::System`, is
+//!   used.
+//! * Otherwise, the global allocator is used, as exposed by `alloc::alloc`.
+//!
+//! The `jemalloc` and `mimalloc` features are mutually exclusive. Each builds and links the
+//! corresponding library via the `jemalloc-sys` or `libmimalloc-sys` crate respectively.
+//!
+//! Some allocators ignore alignments larger than those they guarantee for every allocation. To
+//! honor such alignments portably, they are handled here rather than being passed on to the
+//! backing allocator: a larger block of memory is allocated, a suitably-aligned pointer within it
+//! is returned, and the address of the larger block is stored just before that pointer so that it
+//! can be freed later.
 
-#![cfg_attr(feature = "clippy", allow(inline_always))]
+#![allow(clippy::inline_always)]
 
-use core::isize;
+use core::{cmp, mem, ptr};
+use core::mem::{align_of, size_of};
+use core::alloc::Layout;
+use core::sync::atomic::{AtomicPtr, Ordering};
 use super::error::Error;
 use super::result::Result;
 
-extern "C" {
-    #[allocator]
-    fn __rust_allocate(len: usize, align: usize) -> *mut u8;
-    fn __rust_allocate_zeroed(len: usize, align: usize) -> *mut u8;
-    fn __rust_deallocate(ptr: *mut u8, old_len: usize, align: usize);
-    fn __rust_reallocate(ptr: *mut u8, old_len: usize, len: usize, align: usize) -> *mut u8;
-    fn __rust_reallocate_inplace(ptr: *mut u8, old_len: usize, len: usize, align: usize) -> usize;
+#[cfg(all(feature = "jemalloc", feature = "mimalloc"))]
+compile_error!("the `jemalloc` and `mimalloc` features are mutually exclusive");
+
+#[cfg(not(any(feature = "std", feature = "jemalloc", feature = "mimalloc")))]
+/// The global allocator.
+mod backend {
+    pub use alloc::alloc::{alloc, alloc_zeroed, dealloc, realloc};
+    use core::alloc::Layout;
+
+    #[inline(always)]
+    /// Returns the length of the block of memory that would back an allocation with the specified
+    /// layout. No size class information is available, so this is the length of the layout.
+    pub unsafe fn usable_size(layout: Layout) -> usize {
+        layout.size()
+    }
+}
+
+#[cfg(all(feature = "std", not(any(feature = "jemalloc", feature = "mimalloc"))))]
+/// The system allocator.
+mod backend {
+    use std::alloc::{GlobalAlloc, Layout, System};
+
+    #[inline(always)]
+    /// Allocates a block of memory using the specified layout.
+    pub unsafe fn alloc(layout: Layout) -> *mut u8 {
+        System.alloc(layout)
+    }
+
+    #[inline(always)]
+    /// Allocates a block of memory with all bytes initialized to zero, using the specified layout.
+    pub unsafe fn alloc_zeroed(layout: Layout) -> *mut u8 {
+        System.alloc_zeroed(layout)
+    }
+
+    #[inline(always)]
+    /// Deallocates a block of memory.
+    pub unsafe fn dealloc(ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    #[inline(always)]
+    /// Resizes an existing allocation to the specified length.
+    pub unsafe fn realloc(ptr: *mut u8, layout: Layout, len: usize) -> *mut u8 {
+        System.realloc(ptr, layout, len)
+    }
+
+    #[inline(always)]
+    /// Returns the length of the block of memory that would back an allocation with the specified
+    /// layout. No size class information is available, so this is the length of the layout.
+    pub unsafe fn usable_size(layout: Layout) -> usize {
+        layout.size()
+    }
+}
+
+#[cfg(feature = "jemalloc")]
+/// The jemalloc allocator.
+mod backend {
+    use core::alloc::Layout;
+    use core::ffi::c_void;
+    use jemalloc_sys::{mallocx, nallocx, rallocx, sdallocx, MALLOCX_ALIGN, MALLOCX_ZERO};
+
+    #[inline(always)]
+    /// Allocates a block of memory using the specified layout.
+    pub unsafe fn alloc(layout: Layout) -> *mut u8 {
+        mallocx(layout.size(), MALLOCX_ALIGN(layout.align())) as *mut u8
+    }
+
+    #[inline(always)]
+    /// Allocates a block of memory with all bytes initialized to zero, using the specified layout.
+    pub unsafe fn alloc_zeroed(layout: Layout) -> *mut u8 {
+        mallocx(layout.size(), MALLOCX_ALIGN(layout.align()) | MALLOCX_ZERO) as *mut u8
+    }
+
+    #[inline(always)]
+    /// Deallocates a block of memory.
+    pub unsafe fn dealloc(ptr: *mut u8, layout: Layout) {
+        sdallocx(ptr as *mut c_void, layout.size(), MALLOCX_ALIGN(layout.align()))
+    }
+
+    #[inline(always)]
+    /// Resizes an existing allocation to the specified length.
+    pub unsafe fn realloc(ptr: *mut u8, layout: Layout, len: usize) -> *mut u8 {
+        rallocx(ptr as *mut c_void, len, MALLOCX_ALIGN(layout.align())) as *mut u8
+    }
+
+    #[inline(always)]
+    /// Returns the length of the block of memory that would back an allocation with the specified
+    /// layout.
+    pub unsafe fn usable_size(layout: Layout) -> usize {
+        match nallocx(layout.size(), MALLOCX_ALIGN(layout.align())) {
+            0 => layout.size(),
+            size => size,
+        }
+    }
+}
+
+#[cfg(all(feature = "mimalloc", not(feature = "jemalloc")))]
+/// The mimalloc allocator.
+mod backend {
+    use core::cmp;
+    use core::alloc::Layout;
+    use core::ffi::c_void;
+    use libmimalloc_sys::{
+        mi_free, mi_good_size, mi_malloc_aligned, mi_realloc_aligned, mi_zalloc_aligned,
+    };
+
+    #[inline(always)]
+    /// Allocates a block of memory using the specified layout.
+    pub unsafe fn alloc(layout: Layout) -> *mut u8 {
+        mi_malloc_aligned(layout.size(), layout.align()) as *mut u8
+    }
+
+    #[inline(always)]
+    /// Allocates a block of memory with all bytes initialized to zero, using the specified layout.
+    pub unsafe fn alloc_zeroed(layout: Layout) -> *mut u8 {
+        mi_zalloc_aligned(layout.size(), layout.align()) as *mut u8
+    }
+
+    #[inline(always)]
+    /// Deallocates a block of memory.
+    pub unsafe fn dealloc(ptr: *mut u8, _layout: Layout) {
+        mi_free(ptr as *mut c_void)
+    }
+
+    #[inline(always)]
+    /// Resizes an existing allocation to the specified length.
+    pub unsafe fn realloc(ptr: *mut u8, layout: Layout, len: usize) -> *mut u8 {
+        mi_realloc_aligned(ptr as *mut c_void, len, layout.align()) as *mut u8
+    }
+
+    #[inline(always)]
+    /// Returns the length of the block of memory that would back an allocation with the specified
+    /// layout.
+    pub unsafe fn usable_size(layout: Layout) -> usize {
+        cmp::max(mi_good_size(layout.size()), layout.size())
+    }
+}
+
+#[cfg(feature = "stats")]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+/// A snapshot of statistics about memory allocated via this module.
+pub struct Stats {
+    /// The total number of allocations made, including zeroed allocations.
+    pub allocations: usize,
+    /// The total number of deallocations made.
+    pub deallocations: usize,
+    /// The number of bytes currently allocated.
+    pub live_bytes: usize,
+    /// The largest number of bytes allocated at any one time.
+    pub peak_live_bytes: usize,
+}
+
+#[cfg(feature = "stats")]
+/// Returns a snapshot of statistics about memory allocated via this module.
+///
+/// The counters are updated independently of each other, so a snapshot taken while other threads
+/// are allocating memory may not be entirely consistent.
+pub fn stats() -> Stats {
+    counters::snapshot()
+}
+
+#[cfg(feature = "stats")]
+/// Counters for memory allocated via this module.
+mod counters {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use super::Stats;
+
+    /// The total number of allocations made.
+    static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+    /// The total number of deallocations made.
+    static DEALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+    /// The number of bytes currently allocated.
+    static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+    /// The largest number of bytes allocated at any one time.
+    static PEAK_LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+    #[inline(always)]
+    /// Records an increase in the number of bytes currently allocated.
+    fn add_live_bytes(len: usize) {
+        let live = LIVE_BYTES.fetch_add(len, Ordering::Relaxed) + len;
+        let _ = PEAK_LIVE_BYTES.fetch_max(live, Ordering::Relaxed);
+    }
+
+    #[inline(always)]
+    /// Records a successful allocation.
+    pub fn allocated(len: usize) {
+        let _ = ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        add_live_bytes(len);
+    }
+
+    #[inline(always)]
+    /// Records a successful reallocation.
+    pub fn reallocated(old_len: usize, len: usize) {
+        if len > old_len {
+            add_live_bytes(len - old_len);
+        } else {
+            let _ = LIVE_BYTES.fetch_sub(old_len - len, Ordering::Relaxed);
+        }
+    }
+
+    #[inline(always)]
+    /// Records a deallocation.
+    pub fn deallocated(len: usize) {
+        let _ = DEALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        let _ = LIVE_BYTES.fetch_sub(len, Ordering::Relaxed);
+    }
+
+    /// Returns the current values of all counters.
+    pub fn snapshot() -> Stats {
+        Stats {
+            allocations: ALLOCATIONS.load(Ordering::Relaxed),
+            deallocations: DEALLOCATIONS.load(Ordering::Relaxed),
+            live_bytes: LIVE_BYTES.load(Ordering::Relaxed),
+            peak_live_bytes: PEAK_LIVE_BYTES.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(not(feature = "stats"))]
+/// Counters for memory allocated via this module, compiled out because the `stats` feature is
+/// disabled.
+mod counters {
+    #[inline(always)]
+    /// Records a successful allocation.
+    pub fn allocated(_: usize) {}
+
+    #[inline(always)]
+    /// Records a successful reallocation.
+    pub fn reallocated(_: usize, _: usize) {}
+
+    #[inline(always)]
+    /// Records a deallocation.
+    pub fn deallocated(_: usize) {}
+}
+
+/// The largest alignment that the backing allocator is assumed to honor for every allocation.
+///
+/// This matches the minimum alignment of `malloc` on common platforms.
+const MAX_ALIGN: usize = 2 * size_of::<usize>();
+
+#[inline]
+/// Returns the largest alignment that the backing allocator is assumed to honor for any length.
+///
+/// Allocations with an alignment at or below this value are passed straight to the backing
+/// allocator, and never take the over-aligned path described in the module documentation.
+pub const fn max_align() -> usize {
+    MAX_ALIGN
+}
+
+#[inline]
+/// Returns a sensible alignment for an allocation of the specified length, for use when the caller
+/// has no particular alignment requirement.
+///
+/// This is the smallest power of two no less than `size`, capped at `max_align()`. Small
+/// allocations are thus aligned to their own size, so that they never straddle more cache lines
+/// than necessary, while larger allocations get the largest alignment the backing allocator honors
+/// directly, never taking the costlier over-aligned path described in the module documentation.
+pub fn alignment_for(size: usize) -> usize {
+    size.checked_next_power_of_two()
+        .map_or(MAX_ALIGN, |align| cmp::min(align, MAX_ALIGN))
+}
+
+/// The function called when an allocation fails, or null if there is none.
+static OOM_HANDLER: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+/// Sets the function called when an allocation fails, returning the previous one, if any.
+///
+/// When an allocation, zeroed allocation or reallocation fails because the backing allocator
+/// returned a null pointer, the handler is called once. If it returns `true`, for example after
+/// freeing cached memory, the operation is retried once; otherwise `Error::NotEnoughMemory` is
+/// returned as usual. Passing `None` removes the current handler.
+///
+/// The handler must not allocate memory via this crate itself, since it may be called from within
+/// an allocation that is already failing.
+pub fn set_oom_handler(handler: Option<fn() -> bool>) -> Option<fn() -> bool> {
+    let new = handler.map_or(ptr::null_mut(), |f| f as *mut ());
+    unsafe { oom_handler_from_ptr(OOM_HANDLER.swap(new, Ordering::AcqRel)) }
 }
 
 #[inline(always)]
-/// Performs sanity checks on the length and alignment of a requested memory allocation.
-fn check_len_and_align(len: usize, align: usize) -> Result<()> {
-    #[cfg_attr(feature = "clippy", allow(cast_sign_loss))]
+/// Converts a pointer stored in `OOM_HANDLER` back into the function it was created from.
+unsafe fn oom_handler_from_ptr(ptr: *mut ()) -> Option<fn() -> bool> {
+    if ptr.is_null() {
+        None
+    } else {
+        Some(mem::transmute::<*mut (), fn() -> bool>(ptr))
+    }
+}
+
+#[inline]
+/// Checks whether an allocation with the specified length and alignment would be accepted by the
+/// functions in this module, without allocating any memory.
+///
+/// Returns `Error::ZeroLength` if the length is zero, `Error::BadAlignment` if the alignment is
+/// not a power of two, or `Error::NotEnoughMemory` if the length is too large to ever be
+/// allocated. Passing validation does not guarantee that an allocation will succeed, since the
+/// backing allocator may still run out of memory.
+pub fn validate(len: usize, align: usize) -> Result<()> {
+    check_len_and_align(len, align).map(|_| ())
+}
+
+#[inline(always)]
+/// Performs sanity checks on the length and alignment of a requested memory allocation, returning
+/// the corresponding layout.
+fn check_len_and_align(len: usize, align: usize) -> Result<Layout> {
+    #[allow(clippy::cast_sign_loss)]
     #[inline(always)]
     /// Performs sanity checks on the length of a requested memory allocation.
     fn check_len(len: usize) -> Result<()> {
@@ -48,7 +363,9 @@ fn check_len_and_align(len: usize, align: usize) -> Result<()> {
         }
     }
 
-    check_len(len).and_then(|_| check_align(align))
+    check_len(len)
+        .and_then(|_| check_align(align))
+        .and_then(|_| Layout::from_size_align(len, align).map_err(|_| Error::NotEnoughMemory))
 }
 
 #[inline(always)]
@@ -61,17 +378,177 @@ fn check_ptr(ptr: *mut u8) -> Result<*mut u8> {
     }
 }
 
+#[inline(always)]
+/// Calls an allocation function and checks the pointer it returns, retrying once if the
+/// out-of-memory handler requests it.
+fn check_ptr_with_retry<F: FnMut() -> *mut u8>(mut f: F) -> Result<*mut u8> {
+    check_ptr(f()).or_else(
+        |e| {
+            let handler = unsafe { oom_handler_from_ptr(OOM_HANDLER.load(Ordering::Acquire)) };
+            match handler {
+                Some(handler) if handler() => check_ptr(f()),
+                _ => Err(e),
+            }
+        }
+    )
+}
+
+#[inline(always)]
+/// Returns the layout of the larger block of memory backing an over-aligned allocation.
+///
+/// This leaves room for padding up to the requested alignment, and for storing the address of the
+/// larger block of memory before the aligned pointer.
+fn over_aligned_layout(layout: Layout) -> Result<Layout> {
+    layout
+        .size()
+        .checked_add(layout.align() + size_of::<*mut u8>())
+        .ok_or(Error::CapacityOverflow)
+        .and_then(
+            |size| {
+                Layout::from_size_align(size, align_of::<*mut u8>())
+                    .map_err(|_| Error::NotEnoughMemory)
+            }
+        )
+}
+
+#[inline]
+/// Allocates a block of memory via the backing allocator, handling alignments larger than
+/// `MAX_ALIGN` as described in the module documentation.
+unsafe fn allocate_raw(layout: Layout, zeroed: bool) -> Result<*mut u8> {
+    let alloc = |layout| if zeroed {
+        backend::alloc_zeroed(layout)
+    } else {
+        backend::alloc(layout)
+    };
+    if layout.align() <= MAX_ALIGN {
+        return check_ptr_with_retry(|| alloc(layout));
+    }
+    over_aligned_layout(layout)
+        .and_then(|base_layout| check_ptr_with_retry(|| alloc(base_layout)))
+        .map(
+            |base| {
+                let header = size_of::<*mut u8>();
+                let padding = (base as usize + header).wrapping_neg() & (layout.align() - 1);
+                let ptr = base.add(header + padding);
+                (ptr as *mut *mut u8).sub(1).write(base);
+                ptr
+            }
+        )
+}
+
+#[inline]
+/// Deallocates a block of memory allocated via `allocate_raw`.
+unsafe fn deallocate_raw(ptr: *mut u8, layout: Layout) {
+    if layout.align() <= MAX_ALIGN {
+        backend::dealloc(ptr, layout)
+    } else {
+        let base = (ptr as *mut *mut u8).sub(1).read();
+        let base_layout = Layout::from_size_align_unchecked(
+            layout.size() + layout.align() + size_of::<*mut u8>(),
+            align_of::<*mut u8>(),
+        );
+        backend::dealloc(base, base_layout)
+    }
+}
+
 #[inline]
 /// Allocates a block of memory using the specified length and alignment.
+///
+/// # Safety
+///
+/// The returned block of memory is uninitialized, and must be freed via `deallocate` using the same
+/// length and alignment.
 pub unsafe fn allocate(len: usize, align: usize) -> Result<*mut u8> {
-    check_len_and_align(len, align).and_then(|_| check_ptr(__rust_allocate(len, align)))
+    check_len_and_align(len, align)
+        .and_then(|layout| allocate_raw(layout, false))
+        .inspect(|_| counters::allocated(len))
 }
 
 #[inline]
 /// Allocates a block of memory with all bytes initialized to zero, using the specified length
 /// and alignment.
+///
+/// # Safety
+///
+/// The returned block of memory must be freed via `deallocate` using the same length and
+/// alignment.
 pub unsafe fn allocate_zeroed(len: usize, align: usize) -> Result<*mut u8> {
-    check_len_and_align(len, align).and_then(|_| check_ptr(__rust_allocate_zeroed(len, align)))
+    check_len_and_align(len, align)
+        .and_then(|layout| allocate_raw(layout, true))
+        .inspect(|_| counters::allocated(len))
+}
+
+#[inline]
+/// Returns the length of the block of memory that the backing allocator would actually provide for
+/// an allocation with the specified length and alignment.
+///
+/// Allocators commonly round requests up to a size class, so this may be larger than `len`. Where
+/// the backing allocator cannot report this, or the allocation would be over-aligned as described
+/// in the module documentation, `len` is returned unchanged. `len` is also returned if the length
+/// or alignment is invalid.
+///
+/// For a block of memory allocated via this module using `len` and `align`, every byte up to the
+/// returned length may be used.
+pub fn usable_size(len: usize, align: usize) -> usize {
+    match check_len_and_align(len, align) {
+        Ok(layout) if align <= MAX_ALIGN => unsafe { backend::usable_size(layout) },
+        _ => len,
+    }
+}
+
+#[inline(always)]
+/// Computes the length in bytes of an array of `count` elements, each `elem_size` bytes long.
+fn array_len(count: usize, elem_size: usize) -> Result<usize> {
+    count.checked_mul(elem_size).ok_or(Error::CapacityOverflow)
+}
+
+#[inline]
+/// Allocates a block of memory large enough to hold `count` elements, each `elem_size` bytes long,
+/// using the specified alignment.
+///
+/// Returns `Error::CapacityOverflow` if the length in bytes of the array would overflow.
+///
+/// # Safety
+///
+/// The returned block of memory is uninitialized, and must be freed via `deallocate` using a
+/// length of `count * elem_size` bytes and the same alignment.
+pub unsafe fn allocate_array(count: usize, elem_size: usize, align: usize) -> Result<*mut u8> {
+    array_len(count, elem_size).and_then(|len| allocate(len, align))
+}
+
+#[inline]
+/// Allocates a block of memory using the specified length, aligned to at least `min_align` and,
+/// where this comes at no extra cost, to `want_align`.
+///
+/// The block of memory is allocated using `want_align` if it is no larger than either `min_align`
+/// or `max_align()`, since the backing allocator then honors it directly; otherwise it is
+/// allocated using `min_align`, avoiding the over-aligned path described in the module
+/// documentation wherever `min_align` allows. The returned pointer may still happen to be aligned
+/// to `want_align` in the latter case.
+///
+/// Returns the pointer along with the usable size of the block of memory, as reported by
+/// `usable_size`, and the alignment it was allocated with.
+///
+/// Returns `Error::BadAlignment` if either alignment is not a power of two.
+///
+/// # Safety
+///
+/// The returned block of memory is uninitialized, and must be freed via `deallocate` using `len`
+/// and the returned alignment.
+pub unsafe fn allocate_at_least(
+    len: usize,
+    min_align: usize,
+    want_align: usize,
+) -> Result<(*mut u8, usize, usize)> {
+    if !(min_align.is_power_of_two() && want_align.is_power_of_two()) {
+        return Err(Error::BadAlignment);
+    }
+    let align = if want_align <= cmp::max(min_align, MAX_ALIGN) {
+        cmp::max(min_align, want_align)
+    } else {
+        min_align
+    };
+    allocate(len, align).map(|ptr| (ptr, usable_size(len, align), align))
 }
 
 #[inline]
@@ -83,14 +560,97 @@ pub unsafe fn allocate_zeroed(len: usize, align: usize) -> Result<*mut u8> {
 /// If successful, the memory at `ptr` is undefined.
 ///
 /// On failure, returns an `Error` without affecting the existing allocation.
+///
+/// # Safety
+///
+/// `ptr` must refer to a block of memory allocated via this module using `old_len` and `align`.
+/// If successful, `ptr` must no longer be used, and the returned block of memory must be freed
+/// via `deallocate` using `len` and `align`.
 pub unsafe fn reallocate(
     ptr: *mut u8,
     old_len: usize,
     len: usize,
     align: usize,
 ) -> Result<*mut u8> {
-    check_len_and_align(len, align)
-        .and_then(|_| check_ptr(__rust_reallocate(ptr, old_len, len, align)))
+    check_len_and_align(len, align).and_then(
+        |layout| {
+            let old_layout = Layout::from_size_align_unchecked(old_len, align);
+            if align <= MAX_ALIGN {
+                check_ptr_with_retry(|| backend::realloc(ptr, old_layout, len))
+            } else {
+                allocate_raw(layout, false).inspect(
+                    |&new_ptr| {
+                        ptr::copy_nonoverlapping(ptr, new_ptr, cmp::min(old_len, len));
+                        deallocate_raw(ptr, old_layout);
+                    }
+                )
+            }
+        }
+    ).inspect(|_| counters::reallocated(old_len, len))
+}
+
+#[inline]
+/// Resizes an existing array allocation to hold the specified number of elements, each
+/// `elem_size` bytes long.
+///
+/// The `old_count` and `align` parameters are respectively the number of elements and alignment of
+/// the existing allocation.
+///
+/// Returns `Error::CapacityOverflow` if the length in bytes of the array would overflow. On
+/// failure, returns an `Error` without affecting the existing allocation.
+///
+/// # Safety
+///
+/// The same requirements apply as for `reallocate`, with lengths of `old_count * elem_size` and
+/// `count * elem_size` bytes.
+pub unsafe fn reallocate_array(
+    ptr: *mut u8,
+    old_count: usize,
+    count: usize,
+    elem_size: usize,
+    align: usize,
+) -> Result<*mut u8> {
+    array_len(count, elem_size).and_then(|len| reallocate(ptr, old_count * elem_size, len, align))
+}
+
+#[inline]
+/// Resizes an existing allocation to the specified length, and changes its alignment.
+///
+/// The `old_len` and `old_align` parameters are respectively the length and alignment of the
+/// existing allocation.
+///
+/// Memory must be freed using the same alignment it was allocated with, so unless `align` is equal
+/// to `old_align`, in which case this is equivalent to `reallocate`, a new block of memory is
+/// always allocated, the contents copied across, and the existing block freed. This is so even if
+/// the existing pointer happens to satisfy the new alignment.
+///
+/// Returns `Error::BadAlignment` if either alignment is not a power of two. On failure, returns an
+/// `Error` without affecting the existing allocation.
+///
+/// # Safety
+///
+/// `ptr` must refer to a block of memory allocated via this module using `old_len` and
+/// `old_align`. If successful, `ptr` must no longer be used, and the returned block of memory must
+/// be freed via `deallocate` using `len` and `align`.
+pub unsafe fn reallocate_aligned(
+    ptr: *mut u8,
+    old_len: usize,
+    len: usize,
+    old_align: usize,
+    align: usize,
+) -> Result<*mut u8> {
+    if !old_align.is_power_of_two() {
+        return Err(Error::BadAlignment);
+    }
+    if align == old_align {
+        return reallocate(ptr, old_len, len, align);
+    }
+    allocate(len, align).inspect(
+        |&new_ptr| {
+            ptr::copy_nonoverlapping(ptr, new_ptr, cmp::min(old_len, len));
+            deallocate(ptr, old_len, old_align);
+        }
+    )
 }
 
 #[inline]
@@ -99,18 +659,106 @@ pub unsafe fn reallocate(
 /// The `old_len` and `align` parameters are respectively the length and alignment of the existing
 /// allocation.
 ///
+/// If successful, returns `true` if the allocation now has the specified length, or `false` if it
+/// could not be resized without moving it and still has its old length. In-place resizing is not
+/// currently supported by any backing allocator, so this only returns `true` if `len` is equal to
+/// `old_len`.
+///
 /// On failure, returns an `Error` without affecting the existing allocation.
+///
+/// # Safety
+///
+/// `ptr` must refer to a block of memory allocated via this module using `old_len` and `align`.
 pub unsafe fn reallocate_inplace(
-    ptr: *mut u8,
+    _ptr: *mut u8,
     old_len: usize,
     len: usize,
     align: usize,
-) -> Result<usize> {
-    check_len_and_align(len, align).map(|_| __rust_reallocate_inplace(ptr, old_len, len, align))
+) -> Result<bool> {
+    check_len_and_align(len, align).map(|_| len == old_len)
 }
 
 #[inline]
 /// Deallocates a block of memory.
+///
+/// Since zero-length allocations can never be made via this module, a `len` of zero is treated as
+/// an empty block of memory, and nothing is done. Otherwise, a null `ptr` or an invalid alignment
+/// is caught by an assertion in debug builds.
+///
+/// # Safety
+///
+/// `ptr` must refer to a block of memory allocated via this module using `len` and `align`, and
+/// must not be used again afterwards.
 pub unsafe fn deallocate(ptr: *mut u8, len: usize, align: usize) {
-    __rust_deallocate(ptr, len, align)
+    if len == 0 {
+        return;
+    }
+    debug_assert!(!ptr.is_null(), "attempted to deallocate a null pointer");
+    debug_assert!(align.is_power_of_two(), "attempted to deallocate with an invalid alignment");
+    deallocate_raw(ptr, Layout::from_size_align_unchecked(len, align));
+    counters::deallocated(len);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn array_length_overflow_boundary() {
+        let max_count = usize::MAX / 8;
+        unsafe {
+            assert_eq!(allocate_array(max_count + 1, 8, 8), Err(Error::CapacityOverflow));
+            assert_eq!(allocate_array(max_count, 8, 8), Err(Error::NotEnoughMemory));
+            let ptr = allocate_array(4, 8, 8).unwrap();
+            assert_eq!(
+                reallocate_array(ptr, 4, max_count + 1, 8, 8),
+                Err(Error::CapacityOverflow)
+            );
+            let ptr = reallocate_array(ptr, 4, 8, 8, 8).unwrap();
+            deallocate(ptr, 64, 8);
+        }
+    }
+
+    #[test]
+    fn allocate_at_least_returns_alignment_used() {
+        unsafe {
+            let (ptr, usable, align) = allocate_at_least(32, 2, max_align()).unwrap();
+            assert_eq!(align, max_align());
+            assert!(usable >= 32);
+            assert_eq!(ptr as usize % align, 0);
+            deallocate(ptr, 32, align);
+
+            let (ptr, usable, align) = allocate_at_least(32, 2, max_align() * 2).unwrap();
+            assert_eq!(align, 2);
+            assert!(usable >= 32);
+            assert_eq!(ptr as usize % align, 0);
+            deallocate(ptr, 32, align);
+
+            assert_eq!(allocate_at_least(32, 3, 8), Err(Error::BadAlignment));
+        }
+    }
+
+    #[test]
+    fn over_aligned_allocations() {
+        const ALIGN: usize = 4096;
+        unsafe {
+            let ptr = allocate_zeroed(100, ALIGN).unwrap();
+            assert_eq!(ptr as usize % ALIGN, 0);
+            assert!((0..100).all(|i| *ptr.add(i) == 0));
+            deallocate(ptr, 100, ALIGN);
+
+            let ptr = allocate(100, ALIGN).unwrap();
+            assert_eq!(ptr as usize % ALIGN, 0);
+            for i in 0..100 {
+                ptr.add(i).write(i as u8);
+            }
+            let ptr = reallocate(ptr, 100, 10_000, ALIGN).unwrap();
+            assert_eq!(ptr as usize % ALIGN, 0);
+            assert!((0..100).all(|i| *ptr.add(i) == i as u8));
+            let ptr = reallocate(ptr, 10_000, 50, ALIGN).unwrap();
+            assert_eq!(ptr as usize % ALIGN, 0);
+            assert!((0..50).all(|i| *ptr.add(i) == i as u8));
+            deallocate(ptr, 50, ALIGN);
+        }
+    }
 }
@@ -6,23 +6,27 @@
 // copied, modified, or distributed except according to those terms.
 
 //! Memory allocation in safe Rust, without aborting on failure.
+//!
+//! The default build works on stable Rust. Functionality that depends on unstable language or
+//! library features is only available when the `nightly` feature is enabled. For example, the
+//! `allocator_api` feature, which implies `nightly`, provides an implementation of the unstable
+//! `Allocator` trait.
+//!
+//! By default this crate is `no_std`, and allocates memory via the global allocator. Enabling the
+//! `std` feature makes it allocate memory via the system allocator instead, and implements
+//! `std::error::Error` for `error::Error`. The `jemalloc` and `mimalloc` features instead make it
+//! allocate memory via jemalloc or mimalloc respectively; see the `heap` module for details.
+//!
+//! Enabling the `alloc` feature adds functionality built on the `alloc` crate, namely
+//! `allocation::Allocation::into_boxed_slice` and the `pool` module.
+//!
+//! Enabling the `stats` feature keeps count of the memory allocated via this crate, which can be
+//! inspected via `heap::stats`.
 
 #![no_std]
-#![needs_allocator]
-#![feature(allocator)]
-#![feature(core_intrinsics)]
-#![feature(needs_allocator)]
-#![feature(unique)]
-#![cfg_attr(feature = "clippy", feature(plugin))]
-#![cfg_attr(feature = "clippy", plugin(clippy))]
-#![cfg_attr(feature = "clippy", deny(clippy))]
-#![cfg_attr(feature = "clippy", forbid(clippy_internal))]
-#![cfg_attr(feature = "clippy", deny(clippy_pedantic))]
-#![cfg_attr(feature = "clippy", forbid(clippy_restrictions))]
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
 #![deny(warnings)]
 #![forbid(anonymous_parameters)]
-#![forbid(box_pointers)]
-#![forbid(fat_ptr_transmutes)]
 #![deny(missing_copy_implementations)]
 #![forbid(missing_debug_implementations)]
 #![forbid(missing_docs)]
@@ -35,7 +39,28 @@
 #![forbid(unused_results)]
 #![forbid(variant_size_differences)]
 
+#[cfg(any(
+    feature = "alloc",
+    not(any(feature = "std", feature = "jemalloc", feature = "mimalloc"))
+))]
+extern crate alloc;
+#[cfg(feature = "jemalloc")]
+extern crate jemalloc_sys;
+#[cfg(all(feature = "mimalloc", not(feature = "jemalloc")))]
+extern crate libmimalloc_sys;
+#[cfg(any(feature = "std", test))]
+extern crate std;
+
 pub mod allocation;
+#[cfg(feature = "allocator_api")]
+pub mod allocator;
+pub mod arena;
+pub mod cursor;
 pub mod error;
-mod heap;
+#[cfg(feature = "global_alloc")]
+pub mod global;
+pub mod heap;
+#[cfg(feature = "alloc")]
+pub mod pool;
 pub mod result;
+pub mod typed;
@@ -0,0 +1,178 @@
+// Copyright (c) 2017 FaultyRAM
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT
+// or http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Pools which recycle fixed-size allocations rather than freeing them.
+//!
+//! This module requires the `alloc` feature, since the free list of a pool is a `Vec`.
+
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt;
+use core::mem::ManuallyDrop;
+use core::ops::{Deref, DerefMut};
+use core::ptr;
+use super::allocation::Allocation;
+use super::result::Result;
+
+/// A pool of allocations sharing the same length and alignment.
+///
+/// Blocks handed out via `Pool::get` are returned to the pool when dropped, rather than being
+/// freed, so that later requests can reuse them. At most `max_idle` blocks are kept in the pool;
+/// any more are freed as usual.
+pub struct Pool {
+    /// The length in bytes of each allocation.
+    len: usize,
+    /// The alignment in bytes of each allocation.
+    align: usize,
+    /// The largest number of idle allocations kept for reuse.
+    max_idle: usize,
+    /// The idle allocations available for reuse.
+    idle: RefCell<Vec<Allocation>>,
+}
+
+impl Pool {
+    #[inline]
+    /// Creates an empty pool of allocations with the specified length and alignment, which keeps
+    /// at most `max_idle` of them for reuse.
+    ///
+    /// No memory is allocated until a block is requested via `Pool::get`.
+    pub fn new(len: usize, align: usize, max_idle: usize) -> Pool {
+        Pool {
+            len,
+            align,
+            max_idle,
+            idle: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Returns an idle allocation from the pool, or allocates a new one if there are none.
+    ///
+    /// A newly allocated block is zeroed, whereas a recycled block still holds whatever was last
+    /// written to it, except that any bytes beyond the length it was returned to the pool with are
+    /// zeroed.
+    pub fn get(&self) -> Result<PooledAllocation<'_>> {
+        let recycled = self.idle.borrow_mut().pop();
+        recycled
+            .map_or_else(|| Allocation::zeroed(self.len, self.align), Ok)
+            .map(
+                |inner| {
+                    PooledAllocation {
+                        pool: self,
+                        inner: ManuallyDrop::new(inner),
+                    }
+                }
+            )
+    }
+
+    /// Returns an allocation to the pool, freeing it instead if the pool already holds `max_idle`
+    /// idle allocations, or if it no longer has the length and alignment of the pool.
+    fn recycle(&self, mut alloc: Allocation) {
+        if alloc.capacity() != self.len || alloc.align() != self.align {
+            return;
+        }
+        let mut idle = self.idle.borrow_mut();
+        if idle.len() < self.max_idle && idle.try_reserve(1).is_ok() {
+            unsafe {
+                let len = alloc.len();
+                ptr::write_bytes(alloc.as_mut_ptr().add(len), 0, self.len - len);
+                alloc.set_len(self.len);
+            }
+            idle.push(alloc);
+        }
+    }
+
+    #[inline]
+    /// Returns the number of idle allocations currently held by the pool.
+    pub fn idle(&self) -> usize {
+        self.idle.borrow().len()
+    }
+}
+
+impl fmt::Debug for Pool {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Pool")
+            .field("len", &self.len)
+            .field("align", &self.align)
+            .field("max_idle", &self.max_idle)
+            .field("idle", &self.idle())
+            .finish()
+    }
+}
+
+/// An allocation handed out by a `Pool`, which is returned to the pool when dropped.
+pub struct PooledAllocation<'a> {
+    /// The pool the allocation is returned to.
+    pool: &'a Pool,
+    /// The allocation, which is moved out when it is returned to the pool or detached.
+    inner: ManuallyDrop<Allocation>,
+}
+
+impl<'a> PooledAllocation<'a> {
+    #[inline]
+    /// Returns the allocation to the pool immediately, rather than waiting for it to be dropped.
+    ///
+    /// As with dropping it, the allocation is freed instead if the pool already holds its maximum
+    /// number of idle allocations.
+    pub fn release(self) {}
+
+    #[inline]
+    /// Removes the allocation from the management of the pool, so that it is freed as usual when
+    /// dropped, rather than being returned to the pool.
+    pub fn detach(self) -> Allocation {
+        let mut this = ManuallyDrop::new(self);
+        unsafe { ManuallyDrop::take(&mut this.inner) }
+    }
+}
+
+impl<'a> Deref for PooledAllocation<'a> {
+    type Target = Allocation;
+
+    #[inline]
+    fn deref(&self) -> &Allocation {
+        &self.inner
+    }
+}
+
+impl<'a> DerefMut for PooledAllocation<'a> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Allocation {
+        &mut self.inner
+    }
+}
+
+impl<'a> Drop for PooledAllocation<'a> {
+    fn drop(&mut self) {
+        let inner = unsafe { ManuallyDrop::take(&mut self.inner) };
+        self.pool.recycle(inner);
+    }
+}
+
+impl<'a> fmt::Debug for PooledAllocation<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PooledAllocation")
+            .field("inner", &*self.inner)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recycled_block_keeps_contents_and_zeroes_truncated_tail() {
+        let pool = Pool::new(4, 1, 1);
+        {
+            let mut block = pool.get().unwrap();
+            assert_eq!(block.as_slice(), &[0; 4]);
+            block.fill(0xAB);
+            block.truncate(2);
+        }
+        assert_eq!(pool.idle(), 1);
+        assert_eq!(pool.get().unwrap().as_slice(), &[0xAB, 0xAB, 0, 0]);
+    }
+}
@@ -11,4 +11,7 @@ use core::result;
 use super::error::Error;
 
 /// The result of a memory management operation.
+///
+/// This is an ordinary `core::result::Result` with `Error` as its error type, so `?` works as usual
+/// on any function returning it, as well as on any function whose error type converts into `Error`.
 pub type Result<T> = result::Result<T, Error>;
@@ -0,0 +1,120 @@
+// Copyright (c) 2017 FaultyRAM
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT
+// or http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Types for safely allocating arrays of values.
+
+use core::{fmt, slice};
+use core::marker::PhantomData;
+use core::mem::{align_of, size_of};
+use super::allocation::Allocation;
+use super::error::Error;
+use super::result::Result;
+
+/// An owned, allocated block of memory large enough to hold a fixed number of `T` values.
+///
+/// The elements are never dropped; a `TypedAllocation` only manages the memory backing them.
+pub struct TypedAllocation<T> {
+    /// The underlying byte-oriented allocation.
+    inner: Allocation,
+    /// The number of `T` values the allocation can hold.
+    count: usize,
+    /// Marks this type as owning values of type `T`.
+    marker: PhantomData<T>,
+}
+
+impl<T> TypedAllocation<T> {
+    #[inline]
+    /// Allocates a block of memory large enough to hold `count` values of type `T`.
+    ///
+    /// Returns `Error::CapacityOverflow` if the length in bytes of the array would overflow.
+    pub fn new(count: usize) -> Result<TypedAllocation<T>> {
+        Self::byte_len(count)
+            .and_then(|len| Allocation::new(len, align_of::<T>()))
+            .map(|inner| Self::from_allocation(inner, count))
+    }
+
+    #[inline]
+    /// Allocates a block of memory large enough to hold `count` values of type `T`, with all
+    /// bytes initialized to zero.
+    ///
+    /// Returns `Error::CapacityOverflow` if the length in bytes of the array would overflow.
+    pub fn zeroed(count: usize) -> Result<TypedAllocation<T>> {
+        Self::byte_len(count)
+            .and_then(|len| Allocation::zeroed(len, align_of::<T>()))
+            .map(|inner| Self::from_allocation(inner, count))
+    }
+
+    #[inline]
+    /// Computes the length in bytes of an array of `count` values of type `T`.
+    ///
+    /// The multiplication is checked so that an overflowing count can never wrap around into a
+    /// smaller allocation than the caller expects.
+    fn byte_len(count: usize) -> Result<usize> {
+        count.checked_mul(size_of::<T>()).ok_or(Error::CapacityOverflow)
+    }
+
+    #[inline]
+    /// Wraps an existing allocation known to hold `count` values of type `T`.
+    fn from_allocation(inner: Allocation, count: usize) -> TypedAllocation<T> {
+        TypedAllocation {
+            inner,
+            count,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns the allocated block of memory as a slice of `T`.
+    ///
+    /// # Safety
+    ///
+    /// This is unsafe because the memory may not hold valid values of type `T`, for example if it
+    /// was obtained via `TypedAllocation::new` and has not yet been written to. Undefined behavior
+    /// will occur unless every element has been initialized.
+    pub unsafe fn as_slice(&self) -> &[T] {
+        slice::from_raw_parts(self.inner.as_ptr() as *const T, self.count)
+    }
+
+    /// Returns the allocated block of memory as a mutable slice of `T`.
+    ///
+    /// # Safety
+    ///
+    /// This is unsafe for the same reasons as `TypedAllocation::as_slice`.
+    pub unsafe fn as_mut_slice(&mut self) -> &mut [T] {
+        slice::from_raw_parts_mut(self.inner.as_mut_ptr() as *mut T, self.count)
+    }
+
+    /// Returns the number of `T` values the allocated block of memory can hold.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+impl<T> fmt::Debug for TypedAllocation<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TypedAllocation")
+            .field("inner", &self.inner)
+            .field("count", &self.count)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overflowing_count_is_rejected() {
+        assert_eq!(
+            TypedAllocation::<u32>::new(usize::MAX).err(),
+            Some(Error::CapacityOverflow)
+        );
+        assert_eq!(
+            Allocation::new_array::<u32>(usize::MAX).err(),
+            Some(Error::CapacityOverflow)
+        );
+    }
+}